@@ -8,15 +8,21 @@ const options = ref({
     clear_color: '#252525',
     use_advanced: true,
     star_size: 0.8,
+    motion: 'auto',
 });
 const starSystem = ref(null);
 const starCount = ref(0);
 
 let targetStarCount = 10000;
-const starIncrement = 2000;
+const rampDuration = 5; // Seconds
+
+// Cleared by teardown so an old star system isn't kept alive
+let countInterval = null;
+let onResize = null;
 
 export function useStarSystem() {
     async function setup() {
+        teardown();
         await init();
         if (window.innerWidth < 900) {
             targetStarCount = 2000;
@@ -30,54 +36,45 @@ export function useStarSystem() {
         const stars = new StarSystem(canvas.value, options.value);
         await stars.init();
 
-        window.addEventListener('resize', () => {
+        onResize = () => {
             canvas.value.width = window.innerWidth;
             canvas.value.height = window.innerHeight;
             stars.resize(canvas.value);
-        });
+        };
+        window.addEventListener('resize', onResize);
 
         starSystem.value = stars;
         starCount.value = starSystem.value.get_num_stars();
 
-        window.addEventListener('starCountChanged', () => {
+        // The star system runs its own animation loop, only the count is polled
+        countInterval = setInterval(() => {
             starCount.value = starSystem.value.get_num_stars();
-            // console.log('Updating star count to ' + starCount.value);
-        });
+        }, 250);
 
-        let counter = (targetStarCount - options.value.star_count) / starIncrement;
-        function incrementStars() {
-            counter -= 1;
-            starSystem.value.add_stars(starIncrement);
-            // Emit event that star number has changed
-            window.dispatchEvent(new Event('starCountChanged'));
+        starSystem.value.ramp_to(targetStarCount, rampDuration, 'ease-out');
+        starSystem.value.start();
+    }
 
-            if (counter > 0) {
-                setTimeout(incrementStars, 1000);
-            }
+    function teardown() {
+        clearInterval(countInterval);
+        countInterval = null;
+        if (onResize) {
+            window.removeEventListener('resize', onResize);
+            onResize = null;
         }
-
-        let lastTime = performance.now();
-        function render() {
-            const currentTime = performance.now();
-            const deltaTime = (currentTime - lastTime) / 1000; // Convert to seconds
-            lastTime = currentTime;
-            starSystem.value.update_and_render(deltaTime);
-            requestAnimationFrame(render);
+        if (starSystem.value) {
+            starSystem.value.stop();
+            starSystem.value.free();
+            starSystem.value = null;
         }
-
-        incrementStars();
-        render();
     }
 
     function addStars(count) {
-        console.log(count);
         starSystem.value.add_stars(count);
-        window.dispatchEvent(new Event('starCountChanged'));
     }
 
     function removeStars(count) {
         starSystem.value.remove_stars(count);
-        window.dispatchEvent(new Event('starCountChanged'));
     }
 
     return {
@@ -86,6 +83,7 @@ export function useStarSystem() {
         starSystem,
         starCount,
         setup,
+        teardown,
         addStars,
         removeStars,
     };
@@ -12,6 +12,9 @@ const { initializeWindows } = useWindowManager();
 initializeWindows('Home').catch(console.error);
 
 import { useStarSystem } from '@/composables/useStarSystem';
-const { setup } = useStarSystem();
+const { setup, teardown } = useStarSystem();
 setup().catch(console.error);
+if (import.meta.hot) {
+    import.meta.hot.dispose(teardown);
+}
 
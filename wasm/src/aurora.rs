@@ -0,0 +1,40 @@
+use crate::{nightsky::utils::hex_to_rgba, system_options::StarSystemOptions};
+
+/// Lower edge and upper edge colors used when `aurora_colors` can't be read
+const DEFAULT_COLORS: [[u8; 3]; 2] = [[61, 255, 154], [155, 92, 255]];
+
+/// Look of the aurora layer, shared by the GPU shader and the Canvas2D approximation
+#[derive(Debug, Clone)]
+pub struct AuroraSettings {
+    /// sRGB colors of the lower edge of the curtains and of their tops
+    pub colors: [[u8; 3]; 2],
+    /// Top and bottom of the band the curtains hang in, as fractions of the canvas height
+    pub band: [f32; 2],
+    /// Opacity of the brightest parts of the curtains
+    pub intensity: f32,
+    /// Multiplier for how fast the curtains flow
+    pub speed: f32,
+}
+
+impl AuroraSettings {
+    /// `None` when the aurora is turned off
+    pub fn from_options(options: &StarSystemOptions) -> Option<Self> {
+        if !options.aurora {
+            return None;
+        }
+        let mut colors = DEFAULT_COLORS;
+        for (color, hex) in colors.iter_mut().zip(&options.aurora_colors) {
+            match hex_to_rgba(hex) {
+                Ok([r, g, b, _]) => *color = [r, g, b],
+                Err(e) => log::warn!("Invalid aurora color '{}': {}", hex, e),
+            }
+        }
+        let [top, bottom] = options.aurora_band.map(|edge| edge.clamp(0.0, 1.0));
+        Some(Self {
+            colors,
+            band: [top.min(bottom), top.max(bottom)],
+            intensity: options.aurora_intensity.clamp(0.0, 1.0),
+            speed: options.aurora_speed.max(0.0),
+        })
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+/// A graphics backend the star system can render with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    WebGpu,
+    WebGl,
+    Canvas2d,
+}
+
+impl Backend {
+    /// Parse a backend from its option name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "webgpu" => Some(Backend::WebGpu),
+            "webgl" => Some(Backend::WebGl),
+            "canvas2d" => Some(Backend::Canvas2d),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::WebGpu => "webgpu",
+            Backend::WebGl => "webgl",
+            Backend::Canvas2d => "canvas2d",
+        }
+    }
+
+    /// The wgpu backends used for GPU rendering, `None` for CPU rendering
+    pub fn wgpu_backends(&self) -> Option<wgpu::Backends> {
+        match self {
+            Backend::WebGpu => Some(wgpu::Backends::BROWSER_WEBGPU),
+            Backend::WebGl => Some(wgpu::Backends::GL),
+            Backend::Canvas2d => None,
+        }
+    }
+
+    /// Parse a preference list, skipping unknown names
+    pub fn parse_list(names: &[String]) -> Vec<Self> {
+        names
+            .iter()
+            .filter_map(|name| {
+                let backend = Backend::from_name(name);
+                if backend.is_none() {
+                    log::warn!("Unknown backend '{}', skipping", name);
+                }
+                backend
+            })
+            .collect()
+    }
+}
+
+/// Why a backend earlier in the preference list could not be used
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendFailure {
+    pub backend: String,
+    pub reason: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list_keeps_order_and_skips_unknown_names() {
+        let names = ["canvas2d", "vulkan", "webgpu", "webgl"].map(String::from);
+        assert_eq!(
+            Backend::parse_list(&names),
+            [Backend::Canvas2d, Backend::WebGpu, Backend::WebGl]
+        );
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for backend in [Backend::WebGpu, Backend::WebGl, Backend::Canvas2d] {
+            assert_eq!(Backend::from_name(backend.name()), Some(backend));
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use web_sys::CanvasGradient;
+
+use super::context::Context2d;
+use crate::aurora::AuroraSettings;
+
+/// Number of vertical strips the curtains are split into
+const STRIPS: usize = 64;
+
+/// Canvas2D approximation of the aurora shader
+/// # Info
+/// The curtains are vertical gradient strips whose opacity follows a few sine waves,
+/// drawn with `destination-over` so they end up behind the stars already on the canvas.
+pub struct Curtains {
+    settings: AuroraSettings,
+    /// Flow time, already scaled by the speed
+    time: f64,
+    /// Shared by every strip, rebuilt when the canvas height changes
+    gradient: Option<(u32, CanvasGradient)>,
+}
+
+impl Curtains {
+    pub fn new(settings: AuroraSettings) -> Self {
+        Self {
+            settings,
+            time: 0.0,
+            gradient: None,
+        }
+    }
+
+    /// Let the curtains flow, `delta_time` is already scaled by the motion setting
+    pub fn update(&mut self, delta_time: f64) {
+        self.time += delta_time * self.settings.speed as f64;
+    }
+
+    /// Draw behind whatever is already on the canvas
+    pub fn draw(&mut self, ctx: &Context2d, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        if !matches!(&self.gradient, Some((gradient_height, _)) if *gradient_height == height) {
+            match self.create_gradient(ctx, height) {
+                Ok(gradient) => self.gradient = Some((height, gradient)),
+                Err(e) => {
+                    log::error!("Failed to create the aurora gradient: {}", e);
+                    return;
+                }
+            }
+        }
+        let Some((_, gradient)) = &self.gradient else {
+            return;
+        };
+
+        ctx.save();
+        if let Err(e) = ctx.set_global_composite_operation("destination-over") {
+            log::error!("Failed to draw the aurora: {}", e);
+            ctx.restore();
+            return;
+        }
+        ctx.set_fill_style_gradient(gradient);
+        let [top, bottom] = self.settings.band.map(|edge| edge as f64 * height as f64);
+        let strip_width = width as f64 / STRIPS as f64;
+        let t = self.time * 0.05;
+        for i in 0..STRIPS {
+            let x = i as f64 / STRIPS as f64;
+            let alpha = curtain(x, t) * self.settings.intensity as f64;
+            if alpha < 0.01 {
+                continue;
+            }
+            ctx.set_global_alpha(alpha);
+            // Strips overlap by a pixel to hide the seams
+            ctx.fill_rect(i as f64 * strip_width, top, strip_width + 1.0, bottom - top);
+        }
+        ctx.restore();
+    }
+
+    /// Transparent at the top of the band, through both colors, transparent again at the bottom
+    fn create_gradient(&self, ctx: &Context2d, height: u32) -> Result<CanvasGradient, String> {
+        let [top, bottom] = self.settings.band.map(|edge| edge as f64 * height as f64);
+        let gradient = ctx.create_linear_gradient(0.0, top, 0.0, bottom);
+        let [[lr, lg, lb], [hr, hg, hb]] = self.settings.colors;
+        let stops = [
+            (0.0, format!("rgba({}, {}, {}, 0)", hr, hg, hb)),
+            (0.3, format!("rgb({}, {}, {})", hr, hg, hb)),
+            (0.7, format!("rgb({}, {}, {})", lr, lg, lb)),
+            (1.0, format!("rgba({}, {}, {}, 0)", lr, lg, lb)),
+        ];
+        for (offset, color) in stops {
+            gradient
+                .add_color_stop(offset, &color)
+                .map_err(|e| format!("{:?}", e))?;
+        }
+        Ok(gradient)
+    }
+}
+
+/// Opacity of the curtains at `x` across the canvas, a few sine waves instead of noise
+fn curtain(x: f64, t: f64) -> f64 {
+    let wave = (x * 7.0 + t * 6.0).sin() * 0.5
+        + (x * 13.0 - t * 9.0).sin() * 0.3
+        + (x * 29.0 + t * 17.0).sin() * 0.2;
+    (0.5 + 0.5 * wave).powi(3)
+}
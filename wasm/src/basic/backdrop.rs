@@ -0,0 +1,36 @@
+use super::context::Context2d;
+use crate::day_cycle::DayCycle;
+
+/// Canvas2D day/night sky gradient
+/// # Info
+/// Drawn with `destination-over` after everything else that goes behind the stars,
+/// so it ends up at the very back.
+pub struct Backdrop {
+    pub cycle: DayCycle,
+}
+
+impl Backdrop {
+    pub fn new(cycle: DayCycle) -> Self {
+        Self { cycle }
+    }
+
+    /// Draw behind whatever is already on the canvas
+    pub fn draw(&self, ctx: &Context2d, width: u32, height: u32) -> Result<(), String> {
+        let look = self.cycle.look();
+        let gradient = ctx.create_linear_gradient(0.0, 0.0, 0.0, height as f64);
+        for (offset, color) in [0.0, 1.0].into_iter().zip(look.gradient) {
+            let [r, g, b] = color.map(|c| (c * 255.0).round() as u8);
+            gradient
+                .add_color_stop(offset, &format!("rgb({}, {}, {})", r, g, b))
+                .map_err(|e| format!("{:?}", e))?;
+        }
+        ctx.save();
+        let result = ctx.set_global_composite_operation("destination-over");
+        if result.is_ok() {
+            ctx.set_fill_style_gradient(&gradient);
+            ctx.fill_rect(0.0, 0.0, width as f64, height as f64);
+        }
+        ctx.restore();
+        result
+    }
+}
@@ -0,0 +1,74 @@
+use wasm_bindgen::Clamped;
+use web_sys::{ImageData, OffscreenCanvas};
+
+use super::context::Context2d;
+use crate::{
+    canvas::Canvas,
+    clouds::{CloudSettings, Clouds, CLOUD_OPACITY},
+};
+
+/// Pixels per cloud sample, the canvas smooths the samples when scaling them up
+const CELL: u32 = 16;
+
+/// Canvas2D cloud cover
+/// # Info
+/// Density is sampled once per cell into a small offscreen canvas
+/// that is scaled up over the stars, which already had their brightness
+/// dimmed by `Clouds::visibility`.
+pub struct CloudCanvas {
+    pub clouds: Clouds,
+    canvas: OffscreenCanvas,
+    ctx: Context2d,
+    pixels: Vec<u8>,
+}
+
+impl CloudCanvas {
+    pub fn new(settings: CloudSettings) -> Result<Self, String> {
+        let canvas = OffscreenCanvas::new(1, 1).map_err(|e| format!("{:?}", e))?;
+        let ctx = Context2d::new(&Canvas::Offscreen(canvas.clone()))?;
+        Ok(Self {
+            clouds: Clouds::new(settings),
+            canvas,
+            ctx,
+            pixels: Vec::new(),
+        })
+    }
+
+    /// Draw over whatever is already on the canvas
+    pub fn draw(&mut self, ctx: &Context2d, width: u32, height: u32) -> Result<(), String> {
+        let columns = width.div_ceil(CELL);
+        let rows = height.div_ceil(CELL);
+        if columns == 0 || rows == 0 {
+            return Ok(());
+        }
+        if self.canvas.width() != columns || self.canvas.height() != rows {
+            self.canvas.set_width(columns);
+            self.canvas.set_height(rows);
+        }
+
+        let [r, g, b] = self.clouds.settings.color;
+        self.pixels.clear();
+        for row in 0..rows {
+            for column in 0..columns {
+                // Sample the center of the cell, which is where the scaled up pixel lands
+                let density = self.clouds.density(
+                    (column as f32 + 0.5) * CELL as f32,
+                    (row as f32 + 0.5) * CELL as f32,
+                );
+                let alpha = (density * CLOUD_OPACITY * 255.0).round() as u8;
+                self.pixels.extend_from_slice(&[r, g, b, alpha]);
+            }
+        }
+        let image =
+            ImageData::new_with_u8_clamped_array_and_sh(Clamped(&self.pixels), columns, rows)
+                .map_err(|e| format!("{:?}", e))?;
+        self.ctx.put_image_data(&image, 0, 0)?;
+        ctx.draw_offscreen_canvas(
+            &self.canvas,
+            0.0,
+            0.0,
+            (columns * CELL) as f64,
+            (rows * CELL) as f64,
+        )
+    }
+}
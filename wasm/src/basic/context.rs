@@ -0,0 +1,156 @@
+use wasm_bindgen::JsCast;
+use web_sys::{
+    CanvasGradient, CanvasRenderingContext2d, ImageData, OffscreenCanvas,
+    OffscreenCanvasRenderingContext2d,
+};
+
+use crate::canvas::Canvas;
+
+/// Call the same method on either kind of context
+macro_rules! dispatch {
+    ($context:expr, $ctx:ident => $call:expr) => {
+        match $context {
+            Context2d::Html($ctx) => $call,
+            Context2d::Offscreen($ctx) => $call,
+        }
+    };
+}
+
+/// 2d context of either kind of canvas
+pub enum Context2d {
+    Html(CanvasRenderingContext2d),
+    Offscreen(OffscreenCanvasRenderingContext2d),
+}
+
+impl Context2d {
+    /// A canvas that already has a WebGL or WebGPU context won't give out a 2d one
+    pub fn new(canvas: &Canvas) -> Result<Self, String> {
+        let context = canvas
+            .get_context("2d")?
+            .ok_or("Canvas already has a different context")?;
+        let context = match canvas {
+            Canvas::Html(_) => Context2d::Html(
+                context
+                    .dyn_into::<CanvasRenderingContext2d>()
+                    .map_err(|_| "Context is not a CanvasRenderingContext2d")?,
+            ),
+            Canvas::Offscreen(_) => Context2d::Offscreen(
+                context
+                    .dyn_into::<OffscreenCanvasRenderingContext2d>()
+                    .map_err(|_| "Context is not an OffscreenCanvasRenderingContext2d")?,
+            ),
+        };
+        Ok(context)
+    }
+
+    pub fn put_image_data(&self, image: &ImageData, x: i32, y: i32) -> Result<(), String> {
+        dispatch!(self, ctx => ctx.put_image_data(image, x, y)).map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn get_image_data(&self, width: u32, height: u32) -> Result<ImageData, String> {
+        dispatch!(self, ctx => ctx.get_image_data(0, 0, width as i32, height as i32))
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn save(&self) {
+        dispatch!(self, ctx => ctx.save())
+    }
+
+    pub fn restore(&self) {
+        dispatch!(self, ctx => ctx.restore())
+    }
+
+    pub fn set_global_composite_operation(&self, operation: &str) -> Result<(), String> {
+        dispatch!(self, ctx => ctx.set_global_composite_operation(operation))
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn set_global_alpha(&self, alpha: f64) {
+        dispatch!(self, ctx => ctx.set_global_alpha(alpha))
+    }
+
+    pub fn create_linear_gradient(&self, x0: f64, y0: f64, x1: f64, y1: f64) -> CanvasGradient {
+        dispatch!(self, ctx => ctx.create_linear_gradient(x0, y0, x1, y1))
+    }
+
+    pub fn set_fill_style_gradient(&self, gradient: &CanvasGradient) {
+        dispatch!(self, ctx => ctx.set_fill_style_canvas_gradient(gradient))
+    }
+
+    pub fn fill_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        dispatch!(self, ctx => ctx.fill_rect(x, y, width, height))
+    }
+
+    pub fn set_fill_style_str(&self, style: &str) {
+        dispatch!(self, ctx => ctx.set_fill_style_str(style))
+    }
+
+    pub fn create_radial_gradient(
+        &self,
+        inner: (f64, f64, f64),
+        outer: (f64, f64, f64),
+    ) -> Result<CanvasGradient, String> {
+        let ((x0, y0, r0), (x1, y1, r1)) = (inner, outer);
+        dispatch!(self, ctx => ctx.create_radial_gradient(x0, y0, r0, x1, y1, r1))
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn translate(&self, x: f64, y: f64) -> Result<(), String> {
+        dispatch!(self, ctx => ctx.translate(x, y)).map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn rotate(&self, angle: f64) -> Result<(), String> {
+        dispatch!(self, ctx => ctx.rotate(angle)).map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn begin_path(&self) {
+        dispatch!(self, ctx => ctx.begin_path())
+    }
+
+    pub fn fill(&self) {
+        dispatch!(self, ctx => ctx.fill())
+    }
+
+    /// Add an arc of a circle to the path, angles are in radians clockwise from the x axis
+    pub fn arc(
+        &self,
+        center: (f64, f64),
+        radius: f64,
+        angles: (f64, f64),
+        anticlockwise: bool,
+    ) -> Result<(), String> {
+        let ((x, y), (start, end)) = (center, angles);
+        dispatch!(self, ctx => ctx.arc_with_anticlockwise(x, y, radius, start, end, anticlockwise))
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Add an arc of an axis aligned ellipse to the path
+    pub fn ellipse(
+        &self,
+        center: (f64, f64),
+        radii: (f64, f64),
+        angles: (f64, f64),
+        anticlockwise: bool,
+    ) -> Result<(), String> {
+        let ((x, y), (radius_x, radius_y), (start, end)) = (center, radii, angles);
+        dispatch!(self, ctx => ctx.ellipse_with_anticlockwise(
+            x, y, radius_x, radius_y, 0.0, start, end, anticlockwise,
+        ))
+        .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Draw a whole offscreen canvas scaled into a rectangle
+    pub fn draw_offscreen_canvas(
+        &self,
+        canvas: &OffscreenCanvas,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Result<(), String> {
+        dispatch!(self, ctx => ctx.draw_image_with_offscreen_canvas_and_dw_and_dh(
+            canvas, x, y, width, height,
+        ))
+        .map_err(|e| format!("{:?}", e))
+    }
+}
@@ -1,2 +1,9 @@
+pub mod aurora;
+pub mod backdrop;
+pub mod clouds;
+pub mod context;
+pub mod moon;
+pub mod raster;
 pub mod sky;
+pub mod sky_glow;
 pub mod star;
@@ -0,0 +1,118 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use super::context::Context2d;
+use crate::{
+    moon::{MoonSettings, HALO_FALLOFF},
+    nightsky::utils::{linear_to_srgb, srgb_to_linear},
+};
+
+/// Moon radii the halo gradient reaches out to, where it has all but faded
+const HALO_REACH: f64 = 7.5;
+/// Color stops used to approximate the exponential halo falloff
+const HALO_STOPS: usize = 8;
+
+/// Canvas2D moon
+/// # Info
+/// Drawn with `destination-over`, so it ends up behind the stars and the aurora.
+/// The lit side is a half disc closed by half an ellipse along the terminator,
+/// the unlit side is a full disc behind it.
+pub struct MoonPainter {
+    pub settings: MoonSettings,
+}
+
+impl MoonPainter {
+    pub fn new(settings: MoonSettings) -> Self {
+        Self { settings }
+    }
+
+    /// Draw behind whatever is already on the canvas
+    pub fn draw(&self, ctx: &Context2d, width: u32, height: u32) -> Result<(), String> {
+        let radius = (self.settings.radius * height as f32) as f64;
+        if radius <= 0.0 {
+            return Ok(());
+        }
+        let x = self.settings.position[0] as f64 * width as f64;
+        let y = self.settings.position[1] as f64 * height as f64;
+
+        ctx.save();
+        let result = ctx
+            .set_global_composite_operation("destination-over")
+            .and_then(|_| self.draw_disc(ctx, (x, y), radius))
+            .and_then(|_| self.draw_halo(ctx, (x, y), radius, width, height));
+        ctx.restore();
+        result
+    }
+
+    /// Lit side first so the unlit side goes behind it
+    fn draw_disc(&self, ctx: &Context2d, center: (f64, f64), radius: f64) -> Result<(), String> {
+        let phase = self.settings.phase as f64;
+        let [r, g, b] = self.settings.color;
+
+        ctx.save();
+        ctx.translate(center.0, center.1)?;
+        // A waning moon is lit from the left, the shape is the same turned around
+        if phase > 0.5 {
+            ctx.rotate(PI)?;
+        }
+        // 1.0 at new moon and -1.0 at full moon, the terminator is an ellipse this wide
+        let terminator = (TAU * phase).cos();
+        ctx.begin_path();
+        ctx.arc((0.0, 0.0), radius, (-FRAC_PI_2, FRAC_PI_2), false)?;
+        // A crescent's terminator bulges towards the lit side, a gibbous moon's away from it
+        ctx.ellipse(
+            (0.0, 0.0),
+            (radius * terminator.abs(), radius),
+            (FRAC_PI_2, -FRAC_PI_2),
+            terminator > 0.0,
+        )?;
+        ctx.set_fill_style_str(&format!("rgb({}, {}, {})", r, g, b));
+        ctx.fill();
+        ctx.restore();
+
+        // Scaled in linear space like `moon.wgsl`, so both renderers show the same dark side
+        let earthshine = self.settings.earthshine as f64;
+        let [r, g, b] = self
+            .settings
+            .color
+            .map(|c| linear_to_srgb(srgb_to_linear(c) * earthshine));
+        ctx.begin_path();
+        ctx.arc(center, radius, (0.0, TAU), false)?;
+        ctx.set_fill_style_str(&format!("rgb({}, {}, {})", r, g, b));
+        ctx.fill();
+        Ok(())
+    }
+
+    /// Radial gradient from the edge of the disc following `MoonSettings::glow_at`
+    fn draw_halo(
+        &self,
+        ctx: &Context2d,
+        center: (f64, f64),
+        radius: f64,
+        width: u32,
+        height: u32,
+    ) -> Result<(), String> {
+        let glow = self.settings.glow() as f64;
+        if glow <= 0.0 {
+            return Ok(());
+        }
+        let gradient = ctx.create_radial_gradient(
+            (center.0, center.1, radius),
+            (center.0, center.1, radius * HALO_REACH),
+        )?;
+        let [r, g, b] = self.settings.color;
+        for stop in 0..=HALO_STOPS {
+            let offset = stop as f64 / HALO_STOPS as f64;
+            let distance = offset * (HALO_REACH - 1.0);
+            let alpha = glow * (-distance * HALO_FALLOFF as f64).exp();
+            gradient
+                .add_color_stop(
+                    offset as f32,
+                    &format!("rgba({}, {}, {}, {:.3})", r, g, b, alpha),
+                )
+                .map_err(|e| format!("{:?}", e))?;
+        }
+        ctx.set_fill_style_gradient(&gradient);
+        ctx.fill_rect(0.0, 0.0, width as f64, height as f64);
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+/// Radius steps the sprites are pre-rendered at, in pixels
+const SPRITE_STEP: f64 = 0.25;
+/// Largest sprite radius, stars are at most 2.0 * 4.0 pixels
+const MAX_SPRITE_RADIUS: f64 = 8.0;
+
+/// Pre-rendered coverage of an anti-aliased disc
+struct Sprite {
+    /// Width and height of the sprite, always odd so it centers on a pixel
+    size: i32,
+    coverage: Vec<f32>,
+}
+
+impl Sprite {
+    fn new(radius: f64) -> Self {
+        let half = (radius + 0.5).ceil() as i32;
+        let size = half * 2 + 1;
+        let coverage = (0..size * size)
+            .map(|i| {
+                let dx = (i % size - half) as f64;
+                let dy = (i / size - half) as f64;
+                // Same edge as the GPU fragment shader
+                let dist = (dx * dx + dy * dy).sqrt();
+                (radius - dist + 0.5).clamp(0.0, 1.0) as f32
+            })
+            .collect();
+        Self { size, coverage }
+    }
+}
+
+/// CPU framebuffer in the RGBA layout of `ImageData`
+/// # Info
+/// Stars are stamped from sprites pre-rendered for each radius step,
+/// and only the pixels stamped in the last frame are cleared
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    sprites: Vec<Sprite>,
+    /// Rows touched since the last clear as (y, x start, x end)
+    dirty: Vec<(i32, i32, i32)>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let steps = (MAX_SPRITE_RADIUS / SPRITE_STEP) as usize;
+        Self {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+            sprites: (0..=steps)
+                .map(|step| Sprite::new(step as f64 * SPRITE_STEP))
+                .collect(),
+            dirty: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == self.width && height == self.height {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; (width * height * 4) as usize];
+        self.dirty.clear();
+    }
+
+    /// Clear everything drawn since the last clear back to transparent
+    pub fn clear(&mut self) {
+        for (y, x0, x1) in self.dirty.drain(..) {
+            let row = (y * self.width as i32) as usize * 4;
+            self.pixels[row + x0 as usize * 4..row + x1 as usize * 4].fill(0);
+        }
+    }
+
+    /// Fill every pixel with a background color
+    pub fn fill(&mut self, color: [u8; 4]) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+        self.dirty.clear();
+    }
+
+    /// Sprite index for a star radius in pixels
+    pub fn sprite_for(radius: f64) -> usize {
+        (radius.clamp(0.0, MAX_SPRITE_RADIUS) / SPRITE_STEP).round() as usize
+    }
+
+    /// Index of the largest sprite
+    pub fn max_sprite() -> usize {
+        Self::sprite_for(MAX_SPRITE_RADIUS)
+    }
+
+    /// Blend a disc onto the framebuffer
+    /// Sub-pixel discs keep a one pixel sprite and are dimmed to their area instead
+    pub fn stamp(&mut self, x: f64, y: f64, sprite: usize, color: [u8; 3], alpha: f64) {
+        let sprite_index = sprite.max(Self::sprite_for(0.5));
+        let drawn = sprite_index as f64 * SPRITE_STEP;
+        let radius = sprite as f64 * SPRITE_STEP;
+        let alpha = (alpha * (radius * radius) / (drawn * drawn)).clamp(0.0, 1.0) as f32;
+        if alpha <= 0.0 {
+            return;
+        }
+        let sprite = &self.sprites[sprite_index];
+        let half = sprite.size / 2;
+        let cx = x.floor() as i32;
+        let cy = y.floor() as i32;
+        let x0 = (cx - half).max(0);
+        let x1 = (cx + half + 1).min(self.width as i32);
+        let y0 = (cy - half).max(0);
+        let y1 = (cy + half + 1).min(self.height as i32);
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+        for py in y0..y1 {
+            let row = (py * self.width as i32) as usize * 4;
+            let sprite_row = ((py - cy + half) * sprite.size) as usize;
+            for px in x0..x1 {
+                let a = sprite.coverage[sprite_row + (px - cx + half) as usize] * alpha;
+                if a <= 0.0 {
+                    continue;
+                }
+                let i = row + px as usize * 4;
+                blend(&mut self.pixels[i..i + 4], color, a);
+            }
+            self.dirty.push((py, x0, x1));
+        }
+    }
+}
+
+/// Source-over blend into a non-premultiplied RGBA pixel
+fn blend(pixel: &mut [u8], color: [u8; 3], alpha: f32) {
+    let dst_alpha = pixel[3] as f32 / 255.0;
+    if dst_alpha <= 0.0 {
+        pixel[..3].copy_from_slice(&color);
+        pixel[3] = (alpha * 255.0).round() as u8;
+        return;
+    }
+    let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+    for c in 0..3 {
+        let value =
+            (color[c] as f32 * alpha + pixel[c] as f32 * dst_alpha * (1.0 - alpha)) / out_alpha;
+        pixel[c] = value.round() as u8;
+    }
+    pixel[3] = (out_alpha * 255.0).round() as u8;
+}
@@ -1,104 +1,322 @@
-use super::star::BasicStar;
-use crate::star_render::StarRender;
-use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use super::{
+    aurora::Curtains,
+    backdrop::Backdrop,
+    clouds::CloudCanvas,
+    context::Context2d,
+    moon::MoonPainter,
+    raster::Framebuffer,
+    sky_glow::draw_sky_glow,
+    star::{BasicStar, Lighting},
+};
+use crate::{
+    aurora::AuroraSettings,
+    canvas::Canvas,
+    clouds::CloudSettings,
+    day_cycle::{DayCycle, DayCycleSettings},
+    light_pollution::{limit_visibility, LightPollution, LightPollutionSettings},
+    moon::MoonSettings,
+    motion::Motion,
+    saved_state::SkyState,
+    snapshot::{Snapshot, SnapshotFuture},
+    star_render::{StarCounts, StarRender},
+    stats::RendererInfo,
+    style::StarStyle,
+    utils::random,
+};
+use wasm_bindgen::Clamped;
+use web_sys::ImageData;
 
+/// CPU renderer for when the GPU isn't available
+/// # Info
+/// Stars are stamped into a framebuffer in wasm memory
+/// and copied to the canvas with a single `putImageData` per frame
 pub struct BasicSky {
-    stars: Vec<BasicStar>, // Particles grouped by layers
-    canvas: HtmlCanvasElement,
-    ctx: CanvasRenderingContext2d,
+    stars: Vec<BasicStar>,
+    ctx: Context2d,
+    framebuffer: Framebuffer,
     fade_speed: f64,
-    star_size: f64,
+    style: StarStyle,
+    max_stars: u32,
+    motion: Motion,
+    /// Aurora drawn behind the stars
+    curtains: Option<Curtains>,
+    /// Cloud cover drawn over the stars, also dims the stars behind it
+    clouds: Option<CloudCanvas>,
+    /// Moon and halo drawn behind everything, the halo washes out faint stars
+    moon: Option<MoonPainter>,
+    /// Day/night sky gradient, also fades and tints the stars
+    backdrop: Option<Backdrop>,
+    /// Limiting magnitude that hides faint stars
+    light: LightPollution,
 }
 
 impl BasicSky {
-    pub fn new(canvas_id: &str, fade_speed: f64, star_count: u32, star_size: f32) -> Self {
-        let document = web_sys::window().unwrap().document().unwrap();
-        let canvas = document.get_element_by_id(canvas_id).unwrap();
-        let canvas: HtmlCanvasElement = canvas.dyn_into::<HtmlCanvasElement>().unwrap();
-        let ctx = canvas
-            .get_context("2d")
-            .unwrap()
-            .unwrap()
-            .dyn_into::<CanvasRenderingContext2d>()
-            .unwrap();
-
-        let star_count = star_count.clamp(0, 2000);
+    pub fn new(
+        canvas: &Canvas,
+        fade_speed: f64,
+        star_count: u32,
+        style: StarStyle,
+        max_stars: u32,
+    ) -> Result<Self, String> {
+        let ctx = Context2d::new(canvas)?;
+
+        let star_count = star_count.min(max_stars);
         let stars = (0..star_count)
             .map(|_| {
                 BasicStar::new(
                     canvas.width() as f64,
                     canvas.height() as f64,
                     fade_speed,
-                    js_sys::Math::random(),
-                    "#FFFFFF".to_string(), // Customize colors per layer if needed
-                    star_size.into(),
+                    random(),
+                    star_color(&style),
+                    &style,
                 )
             })
             .collect();
+        let framebuffer = Framebuffer::new(canvas.width(), canvas.height());
 
-        Self {
+        Ok(Self {
             stars,
-            canvas,
             ctx,
+            framebuffer,
             fade_speed,
-            star_size: star_size as f64,
-        }
+            style,
+            max_stars,
+            motion: Motion::Full,
+            curtains: None,
+            clouds: None,
+            moon: None,
+            backdrop: None,
+            light: LightPollution::default(),
+        })
     }
 
-    pub fn update_and_render(&mut self) {
-        self.ctx.clear_rect(
-            0.0,
-            0.0,
-            self.canvas.width() as f64,
-            self.canvas.height() as f64,
-        );
+    pub fn update_and_render(&mut self, delta_time: f64) {
+        self.framebuffer.clear();
 
+        let drift = self.motion.drift() as f64;
+        if let Some(curtains) = self.curtains.as_mut() {
+            curtains.update(delta_time * drift);
+        }
+        if let Some(clouds) = self.clouds.as_mut() {
+            clouds.clouds.update((delta_time * drift) as f32);
+        }
+        self.light.update(delta_time as f32);
+        // The day/night cycle follows the clock whatever the motion setting
+        let mut daylight = Lighting::default();
+        if let Some(backdrop) = self.backdrop.as_mut() {
+            backdrop.cycle.update(delta_time as f32);
+            let look = backdrop.cycle.look();
+            daylight.visibility = look.star_visibility as f64;
+            daylight.tint = look.star_tint;
+        }
+        let width = self.framebuffer.width() as f32;
+        let height = self.framebuffer.height() as f32;
         for star in self.stars.iter_mut() {
             if star.active {
-                star.update();
-                star.draw(&self.ctx);
-            } else {
+                // A static sky is redrawn as it is, without stepping the stars
+                if !self.motion.is_static() {
+                    star.update(delta_time, drift);
+                }
+                let (x, y) = (star.x as f32, star.y as f32);
+                let mut lighting = daylight;
+                lighting.visibility *= limit_visibility(star.magnitude, self.light.limit) as f64;
+                if let Some(clouds) = &self.clouds {
+                    lighting.visibility *= clouds.clouds.visibility(x, y) as f64;
+                }
+                if let Some(moon) = &self.moon {
+                    lighting.glow = moon.settings.glow_at(x, y, width, height) as f64;
+                }
+                star.draw(&mut self.framebuffer, &lighting);
+            } else if !star.dying {
                 *star = BasicStar::new(
-                    self.canvas.width() as f64,
-                    self.canvas.height() as f64,
+                    self.framebuffer.width() as f64,
+                    self.framebuffer.height() as f64,
                     self.fade_speed,
                     0.0,
-                    star.color.clone(),
-                    self.star_size,
+                    star.color,
+                    &self.style,
                 );
             }
         }
+        // Dying stars are dropped once they have faded out
+        self.stars.retain(|star| star.active || !star.dying);
+
+        self.present();
+    }
+
+    /// Copy the framebuffer to the canvas, with the sky glow, the aurora, the moon
+    /// and the backdrop behind it and the clouds over it
+    fn present(&mut self) {
+        let width = self.framebuffer.width();
+        let height = self.framebuffer.height();
+        if width == 0 || height == 0 {
+            return;
+        }
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(self.framebuffer.pixels()),
+            width,
+            height,
+        );
+        match image {
+            Ok(image) => {
+                if let Err(e) = self.ctx.put_image_data(&image, 0, 0) {
+                    log::error!("Failed to draw stars: {}", e);
+                }
+            }
+            Err(e) => log::error!("Failed to create image data: {:?}", e),
+        }
+        if let Err(e) = draw_sky_glow(&self.ctx, &self.light, width, height) {
+            log::error!("Failed to draw the sky glow: {}", e);
+        }
+        if let Some(curtains) = self.curtains.as_mut() {
+            curtains.draw(&self.ctx, width, height);
+        }
+        if let Some(moon) = &self.moon {
+            if let Err(e) = moon.draw(&self.ctx, width, height) {
+                log::error!("Failed to draw the moon: {}", e);
+            }
+        }
+        if let Some(backdrop) = &self.backdrop {
+            if let Err(e) = backdrop.draw(&self.ctx, width, height) {
+                log::error!("Failed to draw the backdrop: {}", e);
+            }
+        }
+        if let Some(clouds) = self.clouds.as_mut() {
+            if let Err(e) = clouds.draw(&self.ctx, width, height) {
+                log::error!("Failed to draw clouds: {}", e);
+            }
+        }
     }
 }
 
 impl StarRender for BasicSky {
-    fn update_and_render(&mut self, _delta_time: f32) {
-        self.update_and_render();
+    fn update_and_render(&mut self, delta_time: f32) {
+        self.update_and_render(delta_time as f64);
     }
 
-    fn resize(&mut self, canvas: HtmlCanvasElement) {
-        self.canvas = canvas;
+    fn resize(&mut self, width: u32, height: u32) {
+        self.framebuffer.resize(width, height);
     }
 
     fn add_stars(&mut self, count: u32) {
+        let count = count.min(self.max_stars.saturating_sub(self.stars.len() as u32));
+        // A static sky never fades new stars in
+        let is_static = self.motion.is_static();
         self.stars.extend((0..count).map(|_| {
             BasicStar::new(
-                self.canvas.width() as f64,
-                self.canvas.height() as f64,
+                self.framebuffer.width() as f64,
+                self.framebuffer.height() as f64,
                 self.fade_speed,
-                0.0,
-                "#FFFFFF".to_string(),
-                self.star_size,
+                if is_static { random() } else { 0.0 },
+                star_color(&self.style),
+                &self.style,
             )
         }));
     }
 
     fn remove_stars(&mut self, count: u32) {
-        self.stars.truncate(self.stars.len().saturating_sub(count as usize));
+        self.stars
+            .iter_mut()
+            .rev()
+            .filter(|star| !star.dying)
+            .take(count as usize)
+            .for_each(|star| star.kill());
+        if self.motion.is_static() {
+            self.stars.retain(|star| !star.dying);
+        }
+    }
+
+    fn get_num_stars(&self) -> StarCounts {
+        let dying = self.stars.iter().filter(|star| star.dying).count();
+        StarCounts {
+            live: (self.stars.len() - dying) as u32,
+            dying: dying as u32,
+        }
     }
 
-    fn get_num_stars(&self) -> u32 {
-        self.stars.len() as u32
+    fn renderer_info(&self) -> RendererInfo {
+        RendererInfo::canvas2d()
     }
+
+    /// Read back the canvas, which has the layers drawn around the stars as well
+    fn snapshot(&mut self) -> SnapshotFuture {
+        let width = self.framebuffer.width();
+        let height = self.framebuffer.height();
+        let snapshot = self
+            .ctx
+            .get_image_data(width, height)
+            .map(|image| Snapshot {
+                width,
+                height,
+                pixels: image.data().0,
+            });
+        Box::pin(std::future::ready(snapshot))
+    }
+
+    fn export_state(&self) -> Option<SkyState> {
+        Some(SkyState::Basic {
+            stars: self.stars.clone(),
+        })
+    }
+
+    fn import_state(&mut self, state: SkyState) -> Result<(), String> {
+        let SkyState::Basic { stars } = state else {
+            return Err(String::from("The state was saved by a GPU renderer"));
+        };
+        self.stars = stars;
+        self.stars.truncate(self.max_stars as usize);
+        if self.motion.is_static() {
+            self.stars.retain(|star| !star.dying);
+        }
+        Ok(())
+    }
+
+    fn set_motion(&mut self, motion: Motion) {
+        self.motion = motion;
+        // Dying stars would never finish fading in a static sky, nor would the limit move
+        if motion.is_static() {
+            self.stars.retain(|star| !star.dying);
+            self.light.settle();
+        }
+    }
+
+    fn set_aurora(&mut self, aurora: Option<AuroraSettings>) {
+        self.curtains = aurora.map(Curtains::new);
+    }
+
+    fn set_clouds(&mut self, clouds: Option<CloudSettings>) {
+        // Keep the clouds and their canvas when only their look changes
+        if let (Some(canvas), Some(settings)) = (self.clouds.as_mut(), &clouds) {
+            canvas.clouds.settings = settings.clone();
+            return;
+        }
+        self.clouds = clouds.and_then(|settings| match CloudCanvas::new(settings) {
+            Ok(clouds) => Some(clouds),
+            Err(e) => {
+                log::error!("Failed to create the cloud canvas: {}", e);
+                None
+            }
+        });
+    }
+
+    fn set_moon(&mut self, moon: Option<MoonSettings>) {
+        self.moon = moon.map(MoonPainter::new);
+    }
+
+    fn set_day_cycle(&mut self, cycle: Option<DayCycleSettings>) {
+        self.backdrop = cycle.map(|settings| Backdrop::new(DayCycle::new(settings)));
+    }
+
+    fn set_light_pollution(&mut self, settings: LightPollutionSettings) {
+        self.light.set(settings);
+        if self.motion.is_static() {
+            self.light.settle();
+        }
+    }
+}
+
+/// A palette color as framebuffer bytes
+fn star_color(style: &StarStyle) -> [u8; 3] {
+    style.random_color().map(|c| (c * 255.0).round() as u8)
 }
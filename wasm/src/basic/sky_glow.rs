@@ -0,0 +1,45 @@
+use super::context::Context2d;
+use crate::light_pollution::LightPollution;
+
+/// Fraction of the height the glow reaches up to, matches `sky_glow.wgsl`
+const GLOW_HEIGHT: f64 = 0.45;
+/// Color stops used to approximate the quadratic falloff
+const GLOW_STOPS: usize = 4;
+
+/// Canvas2D light pollution glow rising from the bottom edge
+/// # Info
+/// Drawn with `destination-over` before the other layers behind the stars,
+/// so it sits right behind the stars and washes out everything further back
+pub fn draw_sky_glow(
+    ctx: &Context2d,
+    light: &LightPollution,
+    width: u32,
+    height: u32,
+) -> Result<(), String> {
+    let glow = light.glow() as f64;
+    if glow <= 0.0 {
+        return Ok(());
+    }
+    let bottom = height as f64;
+    let top = bottom * (1.0 - GLOW_HEIGHT);
+    let gradient = ctx.create_linear_gradient(0.0, bottom, 0.0, top);
+    let [r, g, b] = light.settings.color;
+    for stop in 0..=GLOW_STOPS {
+        let rise = stop as f64 / GLOW_STOPS as f64;
+        let alpha = glow * (1.0 - rise) * (1.0 - rise);
+        gradient
+            .add_color_stop(
+                rise as f32,
+                &format!("rgba({}, {}, {}, {:.3})", r, g, b, alpha),
+            )
+            .map_err(|e| format!("{:?}", e))?;
+    }
+    ctx.save();
+    let result = ctx.set_global_composite_operation("destination-over");
+    if result.is_ok() {
+        ctx.set_fill_style_gradient(&gradient);
+        ctx.fill_rect(0.0, top, width as f64, bottom - top);
+    }
+    ctx.restore();
+    result
+}
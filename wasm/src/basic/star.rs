@@ -1,42 +1,74 @@
-use web_sys::CanvasRenderingContext2d;
+use serde::{Deserialize, Serialize};
 
-use crate::utils::random_range;
+use super::raster::Framebuffer;
+use crate::{
+    light_pollution::{magnitude_scale, random_magnitude},
+    style::StarStyle,
+    utils::random,
+};
 
+/// Frame rate the fade and drift steps are tuned for
+const STEP_RATE: f64 = 60.0;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BasicStar {
     pub x: f64,
     pub y: f64,
-    pub size: f64,
+    /// Framebuffer sprite for the radius of the star
+    pub sprite: usize,
     pub opacity: f64,
+    /// Opacity change per 60th of a second
     pub fade_speed: f64,
     pub active: bool,
-    pub color: String,
+    /// Dying stars fade out and are dropped instead of respawning
+    pub dying: bool,
+    pub color: [u8; 3],
+    /// Pixels moved per 60th of a second
     pub velocity_x: f64,
     pub velocity_y: f64,
+    /// Apparent magnitude, stars fainter than the limiting magnitude are hidden
+    pub magnitude: f32,
 }
 
 impl BasicStar {
-    pub fn new(width: f64, height: f64, fade_speed: f64, opacity: f64, color: String, size: f64) -> Self {
-        let size = size.clamp(0.1, 4.0);
+    pub fn new(
+        width: f64,
+        height: f64,
+        fade_speed: f64,
+        opacity: f64,
+        color: [u8; 3],
+        style: &StarStyle,
+    ) -> Self {
+        // Sizes follow the magnitudes, so the many faint stars are the small ones
+        let magnitude = random_magnitude();
+        let scale = 0.5 + 1.5 * magnitude_scale(magnitude) as f64;
+        let size = style.size.clamp(0.1, 4.0) as f64 * scale;
+        let fade_speed = fade_speed * style.twinkle_speed as f64;
+        let drift = style.drift as f64;
         Self {
-            x: js_sys::Math::random() * width,
-            y: js_sys::Math::random() * height,
-            size: random_range(0.5, 2.0) as f64 * size,
+            x: random() * width,
+            y: random() * height,
+            sprite: Framebuffer::sprite_for(size),
             opacity,
-            fade_speed: (fade_speed / 2.0) + (fade_speed * js_sys::Math::random()),
+            fade_speed: (fade_speed / 2.0) + (fade_speed * random()),
             active: true,
+            dying: false,
             color,
-            velocity_x: (js_sys::Math::random() - 0.5) * 0.08,
-            velocity_y: (js_sys::Math::random() - 0.5) * 0.08,
+            velocity_x: (random() - 0.5) * 0.08 * drift,
+            velocity_y: (random() - 0.5) * 0.08 * drift,
+            magnitude,
         }
     }
 
-    pub fn update(&mut self) {
+    /// Advance the star by `delta_time` seconds, `drift` scales its movement
+    pub fn update(&mut self, delta_time: f64, drift: f64) {
         if !self.active {
             return;
         }
-        self.x += self.velocity_x;
-        self.y += self.velocity_y;
-        self.opacity += self.fade_speed;
+        let steps = delta_time * STEP_RATE;
+        self.x += self.velocity_x * drift * steps;
+        self.y += self.velocity_y * drift * steps;
+        self.opacity += self.fade_speed * steps;
         if self.opacity > 1.0 {
             self.fade_speed = -self.fade_speed;
         } else if self.opacity < 0.0 {
@@ -44,16 +76,97 @@ impl BasicStar {
         }
     }
 
-    pub fn draw(&self, ctx: &CanvasRenderingContext2d) {
+    /// Check the star can be drawn, as a star from a saved state might not be
+    pub fn is_valid(&self) -> bool {
+        [
+            self.x,
+            self.y,
+            self.opacity,
+            self.fade_speed,
+            self.velocity_x,
+            self.velocity_y,
+        ]
+        .iter()
+        .all(|value| value.is_finite())
+            && self.magnitude.is_finite()
+            && self.sprite <= Framebuffer::max_sprite()
+    }
+
+    /// Start fading the star out at its own speed
+    pub fn kill(&mut self) {
+        self.dying = true;
+        self.fade_speed = -self.fade_speed.abs();
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, lighting: &Lighting) {
         if !self.active {
             return;
         }
-        ctx.set_fill_style_str(&self.color);
-        ctx.set_global_alpha(self.opacity);
-        ctx.begin_path();
-        ctx.arc(self.x, self.y, self.size, 0.0, std::f64::consts::PI * 2.0)
-            .unwrap();
-        ctx.fill();
+        let opacity = (self.opacity * lighting.visibility - lighting.glow).max(0.0);
+        let color = [0, 1, 2].map(|i| (self.color[i] as f32 * lighting.tint[i]).round() as u8);
+        framebuffer.stamp(self.x, self.y, self.sprite, color, opacity);
     }
 }
 
+/// How the sky around a star changes the way it is drawn
+#[derive(Debug, Clone, Copy)]
+pub struct Lighting {
+    /// Multiplier for the opacity, for daylight and clouds in front of the star
+    pub visibility: f64,
+    /// Subtracted from the opacity, washes out stars fainter than the sky around them
+    pub glow: f64,
+    /// Multiplier for the color
+    pub tint: [f32; 3],
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            visibility: 1.0,
+            glow: 0.0,
+            tint: [1.0; 3],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn star() -> BasicStar {
+        BasicStar {
+            x: 100.0,
+            y: 100.0,
+            sprite: 0,
+            opacity: 0.5,
+            fade_speed: 0.01,
+            active: true,
+            dying: false,
+            color: [255; 3],
+            velocity_x: 0.04,
+            velocity_y: -0.02,
+            magnitude: 3.0,
+        }
+    }
+
+    #[test]
+    fn steps_follow_the_frame_time() {
+        // One 30 fps frame moves a star as far as two 60 fps frames
+        let (mut slow, mut fast) = (star(), star());
+        slow.update(1.0 / 30.0, 1.0);
+        fast.update(1.0 / 60.0, 1.0);
+        fast.update(1.0 / 60.0, 1.0);
+        assert!((slow.x - fast.x).abs() < 1e-9);
+        assert!((slow.y - fast.y).abs() < 1e-9);
+        assert!((slow.opacity - fast.opacity).abs() < 1e-9);
+        assert!((slow.opacity - 0.52).abs() < 1e-9);
+    }
+
+    #[test]
+    fn drift_only_scales_movement() {
+        let mut star = star();
+        star.update(1.0 / 60.0, 0.0);
+        assert_eq!((star.x, star.y), (100.0, 100.0));
+        assert!(star.opacity > 0.5);
+    }
+}
@@ -0,0 +1,47 @@
+use web_sys::{HtmlCanvasElement, OffscreenCanvas};
+
+/// The canvas a star system renders to
+/// An `OffscreenCanvas` lets the star system run inside a worker
+#[derive(Debug, Clone)]
+pub enum Canvas {
+    Html(HtmlCanvasElement),
+    Offscreen(OffscreenCanvas),
+}
+
+impl Canvas {
+    pub fn width(&self) -> u32 {
+        match self {
+            Canvas::Html(canvas) => canvas.width(),
+            Canvas::Offscreen(canvas) => canvas.width(),
+        }
+    }
+
+    pub fn height(&self) -> u32 {
+        match self {
+            Canvas::Html(canvas) => canvas.height(),
+            Canvas::Offscreen(canvas) => canvas.height(),
+        }
+    }
+
+    pub fn set_size(&self, width: u32, height: u32) {
+        match self {
+            Canvas::Html(canvas) => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+            }
+            Canvas::Offscreen(canvas) => {
+                canvas.set_width(width);
+                canvas.set_height(height);
+            }
+        }
+    }
+
+    /// Get a context from the canvas, `None` if it already has a different kind of context
+    pub fn get_context(&self, context_id: &str) -> Result<Option<js_sys::Object>, String> {
+        match self {
+            Canvas::Html(canvas) => canvas.get_context(context_id),
+            Canvas::Offscreen(canvas) => canvas.get_context(context_id),
+        }
+        .map_err(|e| format!("{:?}", e))
+    }
+}
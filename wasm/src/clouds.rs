@@ -0,0 +1,186 @@
+use crate::{
+    nightsky::{
+        star::{hash, unit_float},
+        utils::hex_to_rgba,
+    },
+    system_options::StarSystemOptions,
+    utils::smoothstep,
+};
+
+/// Pixels per cell of the coarsest noise octave
+pub const CLOUD_SCALE: f32 = 320.0;
+/// Pixels per second the clouds drift at a speed of 1.0
+const CLOUD_DRIFT: f32 = 12.0;
+/// Opacity of the thickest clouds over the sky behind them
+pub const CLOUD_OPACITY: f32 = 0.85;
+/// Width of the soft edge between clear sky and thick cloud, in noise units
+const CLOUD_EDGE: f32 = 0.3;
+/// Color used when `cloud_color` can't be read
+const DEFAULT_COLOR: [u8; 3] = [42, 48, 64];
+
+/// Look of the cloud layer, shared by the GPU shader and the Canvas2D renderer
+#[derive(Debug, Clone)]
+pub struct CloudSettings {
+    /// Fraction of the sky covered, 0.0 is clear and 1.0 overcast
+    pub coverage: f32,
+    /// Pixels per second the clouds move in
+    pub velocity: [f32; 2],
+    /// sRGB color of the clouds
+    pub color: [u8; 3],
+}
+
+impl CloudSettings {
+    /// `None` when the clouds are turned off
+    pub fn from_options(options: &StarSystemOptions) -> Option<Self> {
+        if !options.clouds {
+            return None;
+        }
+        let color = match hex_to_rgba(&options.cloud_color) {
+            Ok([r, g, b, _]) => [r, g, b],
+            Err(e) => {
+                log::warn!("Invalid cloud color '{}': {}", options.cloud_color, e);
+                DEFAULT_COLOR
+            }
+        };
+        // Pixel rows grow downwards, so 90 degrees moves the clouds up
+        let angle = options.cloud_direction.to_radians();
+        let speed = options.cloud_speed.max(0.0) * CLOUD_DRIFT;
+        Some(Self {
+            coverage: options.cloud_coverage.clamp(0.0, 1.0),
+            velocity: [angle.cos() * speed, -angle.sin() * speed],
+            color,
+        })
+    }
+}
+
+/// Drifting cloud cover
+/// # Info
+/// Density is a function of the pixel position and the distance drifted so far,
+/// so each renderer can look it up per star instead of reading back a cloud texture
+/// Mirrors `cloud_density` in `star.wgsl`
+#[derive(Debug, Clone)]
+pub struct Clouds {
+    pub settings: CloudSettings,
+    /// Pixels the clouds have drifted since they were created
+    pub offset: [f32; 2],
+}
+
+impl Clouds {
+    pub fn new(settings: CloudSettings) -> Self {
+        Self {
+            settings,
+            offset: [0.0; 2],
+        }
+    }
+
+    /// Drift the clouds, `delta_time` is already scaled by the motion setting
+    pub fn update(&mut self, delta_time: f32) {
+        for (offset, velocity) in self.offset.iter_mut().zip(self.settings.velocity) {
+            *offset += velocity * delta_time;
+        }
+    }
+
+    /// Cloud thickness from 0.0 to 1.0 at a pixel position, measured from the top left
+    pub fn density(&self, x: f32, y: f32) -> f32 {
+        let coverage = self.settings.coverage;
+        if coverage <= 0.0 {
+            return 0.0;
+        }
+        let p = [
+            (x - self.offset[0]) / CLOUD_SCALE,
+            (y - self.offset[1]) / CLOUD_SCALE,
+        ];
+        let threshold = 1.0 - coverage;
+        smoothstep(threshold, threshold + CLOUD_EDGE, fbm(p))
+    }
+
+    /// How much of a star's brightness gets through the clouds at a pixel position
+    pub fn visibility(&self, x: f32, y: f32) -> f32 {
+        1.0 - self.density(x, y)
+    }
+}
+
+/// Random value at a lattice point, matches `cloud_lattice` in `star.wgsl`
+fn lattice(x: i32, y: i32) -> f32 {
+    unit_float(hash(x as u32 ^ hash(y as u32)))
+}
+
+/// Smoothly interpolated lattice values, matches `cloud_noise` in `star.wgsl`
+fn noise([x, y]: [f32; 2]) -> f32 {
+    let (ix, iy) = (x.floor() as i32, y.floor() as i32);
+    let (fx, fy) = (x - x.floor(), y - y.floor());
+    let (ux, uy) = (fx * fx * (3.0 - 2.0 * fx), fy * fy * (3.0 - 2.0 * fy));
+    let top = lerp(lattice(ix, iy), lattice(ix + 1, iy), ux);
+    let bottom = lerp(lattice(ix, iy + 1), lattice(ix + 1, iy + 1), ux);
+    lerp(top, bottom, uy)
+}
+
+/// Four octaves of noise, matches `cloud_fbm` in `star.wgsl`
+fn fbm([x, y]: [f32; 2]) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 0.5;
+    let mut p = [x, y];
+    for _ in 0..4 {
+        value += amplitude * noise(p);
+        p = [p[0] * 2.0 + 17.0, p[1] * 2.0 + 31.0];
+        amplitude *= 0.5;
+    }
+    // Four octaves add up to at most 0.9375
+    value / 0.9375
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clouds(coverage: f32) -> Clouds {
+        Clouds::new(CloudSettings {
+            coverage,
+            velocity: [10.0, -5.0],
+            color: DEFAULT_COLOR,
+        })
+    }
+
+    #[test]
+    fn density_stays_in_range() {
+        for coverage in [0.0, 0.3, 0.7, 1.0] {
+            let clouds = clouds(coverage);
+            for i in 0..200 {
+                let density = clouds.density(i as f32 * 37.3, i as f32 * 11.9);
+                assert!((0.0..=1.0).contains(&density));
+                assert_eq!(
+                    clouds.visibility(i as f32 * 37.3, i as f32 * 11.9),
+                    1.0 - density
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clear_sky_has_no_clouds() {
+        let clouds = clouds(0.0);
+        assert_eq!(clouds.density(100.0, 200.0), 0.0);
+    }
+
+    #[test]
+    fn more_coverage_is_never_thinner() {
+        let (thin, thick) = (clouds(0.3), clouds(0.8));
+        for i in 0..200 {
+            let (x, y) = (i as f32 * 23.1, i as f32 * 7.7);
+            assert!(thick.density(x, y) >= thin.density(x, y));
+        }
+    }
+
+    #[test]
+    fn clouds_drift_with_their_velocity() {
+        let mut clouds = clouds(0.5);
+        let before = clouds.density(100.0, 100.0);
+        clouds.update(2.0);
+        assert_eq!(clouds.offset, [20.0, -10.0]);
+        assert_eq!(clouds.density(120.0, 90.0), before);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{nightsky::utils::hex_to_rgba, system_options::StarSystemOptions, utils::smoothstep};
+
+/// Seconds between reads of the local clock
+const CLOCK_INTERVAL: f32 = 1.0;
+/// sRGB top and bottom of the sky in full daylight
+const DAY: [[f32; 3]; 2] = [[0.23, 0.5, 0.82], [0.61, 0.79, 0.94]];
+/// sRGB top and bottom of the sky when the sun is on the horizon
+const TWILIGHT: [[f32; 3]; 2] = [[0.16, 0.19, 0.42], [0.94, 0.58, 0.35]];
+/// Night horizon glow, mixed halfway into the clear color
+const NIGHT_HORIZON: [f32; 3] = [0.1, 0.14, 0.25];
+/// Star color multiplier when the sun is on the horizon
+const TWILIGHT_TINT: [f32; 3] = [1.0, 0.86, 0.72];
+
+/// When the sun rises and sets, shared by the GPU and Canvas2D renderers
+#[derive(Debug, Clone)]
+pub struct DayCycleSettings {
+    /// Local hour of the middle of dawn
+    pub sunrise: f32,
+    /// Local hour of the middle of dusk
+    pub sunset: f32,
+    /// Hours dusk and dawn last
+    pub twilight: f32,
+    /// Show this hour instead of following the local clock
+    pub hour: Option<f32>,
+    /// sRGB color of the night sky overhead
+    pub night: [f32; 3],
+}
+
+impl DayCycleSettings {
+    /// `None` when the day/night cycle is turned off
+    /// The night sky takes its color from `clear_color`
+    pub fn from_options(options: &StarSystemOptions) -> Option<Self> {
+        if !options.day_cycle {
+            return None;
+        }
+        let night = match hex_to_rgba(&options.clear_color) {
+            Ok([r, g, b, _]) => [r, g, b].map(|c| c as f32 / 255.0),
+            Err(_) => [0.0; 3],
+        };
+        Some(Self {
+            sunrise: options.sunrise.rem_euclid(24.0),
+            sunset: options.sunset.rem_euclid(24.0),
+            twilight: options.twilight_hours.clamp(0.1, 6.0),
+            hour: options.time_of_day.map(|hour| hour.rem_euclid(24.0)),
+            night,
+        })
+    }
+}
+
+/// Colors and star visibility for a time of day
+#[derive(Debug, Clone, Copy)]
+pub struct SkyLook {
+    /// sRGB top and bottom of the background gradient
+    pub gradient: [[f32; 3]; 2],
+    /// Multiplier for star brightness, stars fade in as the sky darkens
+    pub star_visibility: f32,
+    /// Multiplier for star colors, warmer around dusk and dawn
+    pub star_tint: [f32; 3],
+}
+
+/// Time of day driving the background and the stars
+pub struct DayCycle {
+    pub settings: DayCycleSettings,
+    /// Local hour the sky is showing
+    hour: f32,
+    /// Seconds since the clock was last read
+    since_clock: f32,
+}
+
+impl DayCycle {
+    pub fn new(settings: DayCycleSettings) -> Self {
+        let hour = settings.hour.unwrap_or_else(local_hour);
+        Self {
+            settings,
+            hour,
+            since_clock: 0.0,
+        }
+    }
+
+    /// Follow the local clock, read once a second
+    /// A zero `delta_time` is a redraw of a static sky, which always reads the clock
+    pub fn update(&mut self, delta_time: f32) {
+        if self.settings.hour.is_some() {
+            return;
+        }
+        self.since_clock += delta_time;
+        if self.since_clock >= CLOCK_INTERVAL || delta_time == 0.0 {
+            self.since_clock = 0.0;
+            self.hour = local_hour();
+        }
+    }
+
+    /// 0.0 in full daylight, 1.0 at night
+    pub fn darkness(&self) -> f32 {
+        let settings = &self.settings;
+        let half = settings.twilight / 2.0;
+        // Hours from an event, wrapped to -12 to 12 so the night can cross midnight
+        let since = |event: f32| (self.hour - event + 12.0).rem_euclid(24.0) - 12.0;
+        let after_sunset = smoothstep(-half, half, since(settings.sunset));
+        let before_sunrise = 1.0 - smoothstep(-half, half, since(settings.sunrise));
+        after_sunset.min(before_sunrise)
+    }
+
+    pub fn look(&self) -> SkyLook {
+        let darkness = self.darkness();
+        // Peaks with the sun on the horizon
+        let twilight = 1.0 - (2.0 * darkness - 1.0).abs();
+        let night = [
+            self.settings.night,
+            mix(self.settings.night, NIGHT_HORIZON, 0.5),
+        ];
+        let gradient = [0, 1].map(|edge| {
+            let sky = mix(DAY[edge], night[edge], darkness);
+            mix(sky, TWILIGHT[edge], twilight)
+        });
+        SkyLook {
+            gradient,
+            star_visibility: smoothstep(0.35, 0.9, darkness),
+            star_tint: mix([1.0; 3], TWILIGHT_TINT, twilight),
+        }
+    }
+}
+
+/// Hours since local midnight
+fn local_hour() -> f32 {
+    let now = js_sys::Date::new_0();
+    now.get_hours() as f32 + now.get_minutes() as f32 / 60.0 + now.get_seconds() as f32 / 3600.0
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: f32, sunrise: f32, sunset: f32) -> DayCycle {
+        DayCycle::new(DayCycleSettings {
+            sunrise,
+            sunset,
+            twilight: 1.0,
+            hour: Some(hour),
+            night: [0.0; 3],
+        })
+    }
+
+    #[test]
+    fn dark_at_night_and_light_by_day() {
+        assert_eq!(at(12.0, 6.0, 20.0).darkness(), 0.0);
+        assert_eq!(at(23.5, 6.0, 20.0).darkness(), 1.0);
+        assert_eq!(at(0.5, 6.0, 20.0).darkness(), 1.0);
+        assert_eq!(at(20.0, 6.0, 20.0).darkness(), 0.5);
+        assert_eq!(at(6.0, 6.0, 20.0).darkness(), 0.5);
+    }
+
+    #[test]
+    fn sunset_after_midnight() {
+        assert_eq!(at(1.0, 5.0, 0.5).darkness(), 1.0);
+        assert_eq!(at(23.0, 5.0, 0.5).darkness(), 0.0);
+        assert_eq!(at(12.0, 5.0, 0.5).darkness(), 0.0);
+    }
+
+    #[test]
+    fn stars_only_show_at_night() {
+        assert_eq!(at(12.0, 6.0, 20.0).look().star_visibility, 0.0);
+        assert_eq!(at(2.0, 6.0, 20.0).look().star_visibility, 1.0);
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DedicatedWorkerGlobalScope, Window};
+
+/// Slack allowed when comparing frame timestamps against the frame rate cap
+/// rAF timestamps jitter slightly around the display refresh
+const FRAME_SLACK_MS: f64 = 1.0;
+
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>;
+
+/// Where animation frames are requested from, the page or a worker
+#[derive(Clone)]
+enum Scheduler {
+    Window(Window),
+    Worker(DedicatedWorkerGlobalScope),
+}
+
+impl Scheduler {
+    fn current() -> Result<Self, String> {
+        let global = js_sys::global();
+        if let Some(window) = global.dyn_ref::<Window>() {
+            return Ok(Scheduler::Window(window.clone()));
+        }
+        global
+            .dyn_into::<DedicatedWorkerGlobalScope>()
+            .map(Scheduler::Worker)
+            .map_err(|_| String::from("No requestAnimationFrame in this context"))
+    }
+
+    fn request(&self, callback: &Closure<dyn FnMut(f64)>) -> Result<i32, String> {
+        match self {
+            Scheduler::Window(window) => {
+                window.request_animation_frame(callback.as_ref().unchecked_ref())
+            }
+            Scheduler::Worker(worker) => {
+                worker.request_animation_frame(callback.as_ref().unchecked_ref())
+            }
+        }
+        .map_err(|e| format!("{:?}", e))
+    }
+
+    fn timeout(&self, callback: &js_sys::Function) -> Result<i32, String> {
+        match self {
+            Scheduler::Window(window) => window.set_timeout_with_callback(callback),
+            Scheduler::Worker(worker) => worker.set_timeout_with_callback(callback),
+        }
+        .map_err(|e| format!("{:?}", e))
+    }
+
+    fn cancel(&self, handle: i32) {
+        let result = match self {
+            Scheduler::Window(window) => window.cancel_animation_frame(handle),
+            Scheduler::Worker(worker) => worker.cancel_animation_frame(handle),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to cancel animation frame: {:?}", e);
+        }
+    }
+}
+
+/// A `requestAnimationFrame` loop calling back with the delta time in seconds
+/// The loop stops when this is dropped
+pub struct FrameLoop {
+    scheduler: Scheduler,
+    handle: Rc<Cell<Option<i32>>>,
+    callback: FrameCallback,
+}
+
+impl FrameLoop {
+    /// Start requesting frames
+    /// # Arguments
+    /// - `max_fps` - Skip display refreshes so frames are at most this frequent
+    /// - `on_frame` - Called with the seconds since the previous frame, 0 for the first one
+    pub fn start(
+        max_fps: Option<f32>,
+        mut on_frame: impl FnMut(f32) + 'static,
+    ) -> Result<Self, String> {
+        let scheduler = Scheduler::current()?;
+        let handle = Rc::new(Cell::new(None));
+        let callback: FrameCallback = Rc::new(RefCell::new(None));
+        let min_interval = max_fps
+            .filter(|fps| *fps > 0.0)
+            .map(|fps| 1000.0 / fps as f64);
+
+        let mut last_frame: Option<f64> = None;
+        // When the capped frame was due, moved on by whole intervals so the
+        // time between a due frame and the refresh that shows it isn't lost
+        let mut due = 0.0;
+        let frame = {
+            let scheduler = scheduler.clone();
+            let handle = handle.clone();
+            let callback = callback.clone();
+            move |timestamp: f64| {
+                handle.set(None);
+                if let Some(next) = callback.borrow().as_ref() {
+                    match scheduler.request(next) {
+                        Ok(id) => handle.set(Some(id)),
+                        Err(e) => log::error!("Failed to request animation frame: {}", e),
+                    }
+                }
+                let elapsed = match last_frame {
+                    Some(last) => timestamp - last,
+                    None => 0.0,
+                };
+                if let (Some(min), Some(_)) = (min_interval, last_frame) {
+                    let since_due = timestamp - due;
+                    if since_due < min - FRAME_SLACK_MS {
+                        return;
+                    }
+                    // Start over after a stall rather than catching up with a burst of frames
+                    due = if since_due < 2.0 * min {
+                        due + min
+                    } else {
+                        timestamp
+                    };
+                } else {
+                    due = timestamp;
+                }
+                last_frame = Some(timestamp);
+                on_frame((elapsed / 1000.0) as f32);
+            }
+        };
+        *callback.borrow_mut() = Some(Closure::new(frame));
+
+        if let Some(first) = callback.borrow().as_ref() {
+            handle.set(Some(scheduler.request(first)?));
+        }
+        Ok(Self {
+            scheduler,
+            handle,
+            callback,
+        })
+    }
+}
+
+impl Drop for FrameLoop {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.scheduler.cancel(handle);
+        }
+        // The closure holds a reference to itself to schedule the next frame
+        self.callback.borrow_mut().take();
+    }
+}
+
+/// Wait for a later task of the event loop
+/// WebGL only updates fences, and so buffer mappings, between tasks
+pub async fn next_task() -> Result<(), String> {
+    let scheduler = Scheduler::current()?;
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        if let Err(e) = scheduler.timeout(&resolve) {
+            let _ = reject.call1(&JsValue::NULL, &JsValue::from_str(&e));
+        }
+    });
+    JsFuture::from(promise)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("{:?}", e))
+}
@@ -0,0 +1,282 @@
+use crate::star_render::StarCounts;
+
+/// Frames longer than this are treated as stalls (tab switches, debugger pauses)
+/// and are not counted towards the frame time average
+const MAX_SAMPLE: f32 = 0.5;
+/// Weight of the newest frame in the smoothed frame time
+const SMOOTHING: f32 = 0.1;
+/// How often the governor reconsiders the star count, in seconds
+const ADJUST_INTERVAL: f32 = 1.0;
+/// How long to wait after shedding stars before growing again, in seconds
+const SHED_COOLDOWN: f32 = 3.0;
+/// Frame times within this factor of the budget still count as on budget
+const BUDGET_TOLERANCE: f32 = 1.15;
+/// Frame times beyond this factor of the budget cause stars to be shed
+const SHED_THRESHOLD: f32 = 1.3;
+/// Frames have to get at least this much faster after shedding for shedding to count as working
+const SHED_GAIN: f32 = 0.95;
+/// How long a frame time floor is trusted before shedding is tried again, in seconds
+const FLOOR_HOLD: f32 = 60.0;
+const MIN_STEP: u32 = 250;
+const MAX_STEP: u32 = 2000;
+const MIN_STARS: u32 = 100;
+
+/// Change to the star count requested by the governor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GovernorAction {
+    Hold,
+    Add(u32),
+    Remove(u32),
+}
+
+/// Frame time that shedding stars couldn't get below
+#[derive(Debug, Clone, Copy)]
+struct Floor {
+    frame_time: f32,
+    /// Star count before the shed that didn't help, the sky grows back to it
+    star_count: u32,
+    /// Seconds since the floor was found
+    age: f32,
+}
+
+/// Adaptive performance governor
+/// Measures frame times and grows the star count towards a ceiling while
+/// frames stay within the budget of the target frame rate, shedding stars
+/// when the budget is missed
+/// # Refresh rate
+/// Frames can't come faster than the display refreshes, so a target above the
+/// refresh rate is never met. Removed stars keep drawing while they fade, so a
+/// shed is only judged once its stars are gone. When it didn't make frames any
+/// faster the frame time is taken as a floor, the budget is raised to it and the
+/// sky grows back to the count it had before, but no further. The floor is
+/// dropped after a while in case the stars were the bottleneck after all
+pub struct Governor {
+    budget: f32,
+    max_stars: u32,
+    smoothed: Option<f32>,
+    elapsed: f32,
+    cooldown: f32,
+    /// Frame time and star count before the last shed, to tell if shedding helped
+    shed_from: Option<(f32, u32)>,
+    floor: Option<Floor>,
+}
+
+impl Governor {
+    pub fn new(target_fps: f32, max_stars: u32) -> Self {
+        Self {
+            budget: 1.0 / target_fps.max(1.0),
+            max_stars,
+            smoothed: None,
+            elapsed: 0.0,
+            cooldown: 0.0,
+            shed_from: None,
+            floor: None,
+        }
+    }
+
+    /// Record a frame and decide whether the star count should change
+    pub fn update(&mut self, delta_time: f32, stars: StarCounts) -> GovernorAction {
+        if delta_time <= 0.0 || delta_time > MAX_SAMPLE {
+            return GovernorAction::Hold;
+        }
+        self.cooldown = (self.cooldown - delta_time).max(0.0);
+        if let Some(floor) = &mut self.floor {
+            floor.age += delta_time;
+            if floor.age >= FLOOR_HOLD {
+                self.floor = None;
+            }
+        }
+        // Wait for the shed stars to fade out before measuring the frames without them
+        if self.shed_from.is_some() && stars.dying > 0 {
+            self.smoothed = None;
+            self.elapsed = 0.0;
+            return GovernorAction::Hold;
+        }
+        let smoothed = match self.smoothed {
+            Some(s) => s + (delta_time - s) * SMOOTHING,
+            None => delta_time,
+        };
+        self.smoothed = Some(smoothed);
+        self.elapsed += delta_time;
+        if self.elapsed < ADJUST_INTERVAL {
+            return GovernorAction::Hold;
+        }
+        self.elapsed = 0.0;
+
+        let star_count = stars.live;
+        let (budget, max_stars) = match self.floor {
+            Some(floor) => (
+                self.budget.max(floor.frame_time),
+                self.max_stars.min(floor.star_count),
+            ),
+            None => (self.budget, self.max_stars),
+        };
+        if smoothed > budget * SHED_THRESHOLD {
+            if let Some((before, shed_count)) = self.shed_from.take() {
+                if smoothed > before * SHED_GAIN {
+                    self.floor = Some(Floor {
+                        frame_time: smoothed,
+                        star_count: shed_count,
+                        age: 0.0,
+                    });
+                    return GovernorAction::Hold;
+                }
+            }
+            // Shed in proportion to how far over budget the frames are
+            let over = 1.0 - budget / smoothed;
+            let shed = (star_count as f32 * over * 0.5) as u32;
+            let shed = shed.max(MIN_STEP).min(star_count.saturating_sub(MIN_STARS));
+            if shed == 0 {
+                return GovernorAction::Hold;
+            }
+            self.cooldown = SHED_COOLDOWN;
+            self.shed_from = Some((smoothed, star_count));
+            // Let the average settle on the new star count
+            self.smoothed = None;
+            return GovernorAction::Remove(shed);
+        }
+
+        self.shed_from = None;
+        if smoothed <= budget * BUDGET_TOLERANCE && self.cooldown <= 0.0 && star_count < max_stars {
+            let step = (star_count / 4).clamp(MIN_STEP, MAX_STEP);
+            return GovernorAction::Add(step.min(max_stars - star_count));
+        }
+        GovernorAction::Hold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds removed stars take to fade out in the simulated sky
+    const FADE: f32 = 10.0;
+
+    /// Simulated sky whose frame time depends on the stars it draws
+    struct Sky {
+        live: u32,
+        /// Removed stars and the seconds until they have faded
+        dying: Vec<(u32, f32)>,
+        /// Fewest live stars seen so far
+        min_live: u32,
+        /// Most live stars seen so far
+        max_live: u32,
+    }
+
+    impl Sky {
+        fn new(live: u32) -> Self {
+            Self {
+                live,
+                dying: Vec::new(),
+                min_live: live,
+                max_live: live,
+            }
+        }
+
+        fn counts(&self) -> StarCounts {
+            StarCounts {
+                live: self.live,
+                dying: self.dying.iter().map(|(count, _)| count).sum(),
+            }
+        }
+
+        /// Run the governor for a while, applying what it asks for
+        fn run(&mut self, governor: &mut Governor, seconds: f32, frame_time: impl Fn(u32) -> f32) {
+            let mut time = 0.0;
+            while time < seconds {
+                let counts = self.counts();
+                let delta_time = frame_time(counts.live + counts.dying);
+                time += delta_time;
+                for (_, left) in &mut self.dying {
+                    *left -= delta_time;
+                }
+                self.dying.retain(|(_, left)| *left > 0.0);
+                match governor.update(delta_time, self.counts()) {
+                    GovernorAction::Add(count) => self.live += count,
+                    GovernorAction::Remove(count) => {
+                        self.live -= count;
+                        self.dying.push((count, FADE));
+                    }
+                    GovernorAction::Hold => {}
+                }
+                self.min_live = self.min_live.min(self.live);
+                self.max_live = self.max_live.max(self.live);
+            }
+        }
+    }
+
+    #[test]
+    fn grows_while_on_budget() {
+        let mut governor = Governor::new(60.0, 5000);
+        let mut sky = Sky::new(1000);
+        sky.run(&mut governor, 30.0, |_| 1.0 / 60.0);
+        assert_eq!(sky.live, 5000);
+    }
+
+    #[test]
+    fn sheds_when_over_budget() {
+        let mut governor = Governor::new(60.0, 20000);
+        let mut sky = Sky::new(10000);
+        sky.run(&mut governor, 2.0, |_| 1.0 / 20.0);
+        assert!(sky.live < 10000);
+    }
+
+    #[test]
+    fn sheds_until_the_stars_fit_the_budget() {
+        // Each star costs the same, 10000 stars take 50 ms
+        let mut governor = Governor::new(60.0, 20000);
+        let mut sky = Sky::new(10000);
+        sky.run(&mut governor, 120.0, |stars| stars as f32 / 200_000.0);
+        let frame_time = sky.live as f32 / 200_000.0;
+        assert!(
+            frame_time <= 1.0 / 60.0 * SHED_THRESHOLD,
+            "{} stars",
+            sky.live
+        );
+        assert!(sky.max_live <= 10000);
+    }
+
+    #[test]
+    fn stops_shedding_at_the_refresh_rate() {
+        // A 144 fps target on a 60 Hz display, the frame time never changes
+        let mut governor = Governor::new(144.0, 20000);
+        let mut sky = Sky::new(10000);
+        sky.run(&mut governor, FLOOR_HOLD, |_| 1.0 / 60.0);
+        // One shed to find the floor, then back to where it started
+        let one_shed = (10000.0 * (1.0 - 60.0 / 144.0) * 0.5) as u32;
+        assert_eq!(sky.min_live, 10000 - one_shed);
+        assert_eq!(sky.max_live, 10000);
+        assert_eq!(sky.live, 10000);
+    }
+
+    #[test]
+    fn slow_frames_that_stars_dont_cause_keep_the_count() {
+        // A 60 fps target with every frame taking 50 ms whatever is drawn
+        let mut governor = Governor::new(60.0, 20000);
+        let mut sky = Sky::new(10000);
+        sky.run(&mut governor, FLOOR_HOLD, |_| 0.05);
+        assert!(sky.min_live >= 6000, "shed down to {} stars", sky.min_live);
+        assert_eq!(sky.max_live, 10000);
+    }
+
+    #[test]
+    fn floor_expires() {
+        let mut governor = Governor::new(144.0, 20000);
+        let mut sky = Sky::new(10000);
+        sky.run(&mut governor, FLOOR_HOLD / 2.0, |_| 1.0 / 60.0);
+        assert!(governor.floor.is_some());
+        sky.run(&mut governor, FLOOR_HOLD, |_| 1.0 / 60.0);
+        // Shedding is tried again once the floor has been held long enough
+        assert!(sky.min_live < 10000);
+    }
+
+    #[test]
+    fn ignores_stalls() {
+        let mut governor = Governor::new(60.0, 20000);
+        let stars = StarCounts {
+            live: 1000,
+            dying: 0,
+        };
+        assert_eq!(governor.update(2.0, stars), GovernorAction::Hold);
+    }
+}
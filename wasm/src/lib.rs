@@ -1,10 +1,26 @@
+pub(crate) mod aurora;
+pub(crate) mod backend;
+pub(crate) mod basic;
+pub(crate) mod basic_stars;
+pub(crate) mod canvas;
+pub(crate) mod clouds;
+pub(crate) mod day_cycle;
+pub(crate) mod frame_loop;
+pub(crate) mod governor;
+pub(crate) mod light_pollution;
+pub(crate) mod moon;
+pub(crate) mod motion;
 pub(crate) mod nightsky;
-pub(crate)  mod basic_stars;
-pub(crate) mod utils;
-pub(crate) mod system_options;
+pub(crate) mod ramp;
+pub(crate) mod saved_state;
+pub(crate) mod snapshot;
 pub(crate) mod star_render;
-pub(crate) mod basic;
+pub(crate) mod stats;
+pub(crate) mod style;
+pub(crate) mod system_options;
+pub(crate) mod utils;
+pub(crate) mod visibility;
 
+pub mod offline;
+pub mod star_context;
 pub mod star_system;
-
-
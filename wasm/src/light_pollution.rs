@@ -0,0 +1,170 @@
+use crate::{
+    nightsky::utils::hex_to_rgba,
+    system_options::StarSystemOptions,
+    utils::{random, smoothstep},
+};
+
+/// Magnitude of the brightest stars that are generated
+pub const BRIGHTEST_MAGNITUDE: f32 = -1.0;
+/// Magnitude of the faintest stars, what a dark countryside sky shows
+pub const FAINTEST_MAGNITUDE: f32 = 6.5;
+/// Limiting magnitude without light pollution, past the fade of the faintest stars
+pub const DARK_MAGNITUDE: f32 = FAINTEST_MAGNITUDE + LIMIT_EDGE;
+/// Limiting magnitude under the worst light pollution, an inner city sky
+const CITY_MAGNITUDE: f32 = 2.0;
+/// Magnitudes per second the limit moves by, so stars fade instead of popping
+const LIMIT_SPEED: f32 = 1.5;
+/// Width of the fade around the limit in magnitudes
+const LIMIT_EDGE: f32 = 0.5;
+/// Opacity of the sky glow at the bottom edge under the worst light pollution
+const MAX_GLOW: f32 = 0.6;
+/// Color used when `light_pollution_color` can't be read
+const DEFAULT_COLOR: [u8; 3] = [217, 145, 92];
+
+/// Magnitude for a new star
+/// Each magnitude step has about three times as many stars as the one before,
+/// like the real sky, so most stars are faint
+pub fn random_magnitude() -> f32 {
+    // Inverse of a cumulative count growing by 10^(0.5 m)
+    let k = 0.5;
+    let low = 10f32.powf(k * BRIGHTEST_MAGNITUDE);
+    let high = 10f32.powf(k * FAINTEST_MAGNITUDE);
+    let count = low + random() as f32 * (high - low);
+    (count.log10() / k).clamp(BRIGHTEST_MAGNITUDE, FAINTEST_MAGNITUDE)
+}
+
+/// 0.0 for the faintest stars up to 1.0 for the brightest
+pub fn magnitude_scale(magnitude: f32) -> f32 {
+    (FAINTEST_MAGNITUDE - magnitude) / (FAINTEST_MAGNITUDE - BRIGHTEST_MAGNITUDE)
+}
+
+/// How much of a star at a magnitude shows under a limiting magnitude
+/// Mirrors `limit_visibility` in `star.wgsl`
+pub fn limit_visibility(magnitude: f32, limit: f32) -> f32 {
+    smoothstep(-LIMIT_EDGE, LIMIT_EDGE, limit - magnitude)
+}
+
+/// Amount and color of light pollution, shared by the GPU and Canvas2D renderers
+#[derive(Debug, Clone)]
+pub struct LightPollutionSettings {
+    /// 0.0 for a dark sky up to 1.0 for a city center
+    pub amount: f32,
+    /// sRGB color of the sky glow
+    pub color: [u8; 3],
+}
+
+impl LightPollutionSettings {
+    pub fn from_options(options: &StarSystemOptions) -> Self {
+        let color = match hex_to_rgba(&options.light_pollution_color) {
+            Ok([r, g, b, _]) => [r, g, b],
+            Err(e) => {
+                log::warn!(
+                    "Invalid light pollution color '{}': {}",
+                    options.light_pollution_color,
+                    e
+                );
+                DEFAULT_COLOR
+            }
+        };
+        Self {
+            amount: options.light_pollution.clamp(0.0, 1.0),
+            color,
+        }
+    }
+
+    /// Faintest magnitude that still shows through the pollution
+    pub fn limiting_magnitude(&self) -> f32 {
+        DARK_MAGNITUDE + (CITY_MAGNITUDE - DARK_MAGNITUDE) * self.amount
+    }
+}
+
+/// Light pollution with a limiting magnitude that moves smoothly to its target
+pub struct LightPollution {
+    pub settings: LightPollutionSettings,
+    /// Limiting magnitude the stars are drawn with right now
+    pub limit: f32,
+}
+
+impl LightPollution {
+    pub fn new(settings: LightPollutionSettings) -> Self {
+        Self {
+            limit: settings.limiting_magnitude(),
+            settings,
+        }
+    }
+
+    /// Change the amount, the limit follows over the next frames
+    pub fn set(&mut self, settings: LightPollutionSettings) {
+        self.settings = settings;
+    }
+
+    /// Jump straight to the target limit, for skies without motion
+    pub fn settle(&mut self) {
+        self.limit = self.settings.limiting_magnitude();
+    }
+
+    /// Move the limit towards the target
+    pub fn update(&mut self, delta_time: f32) {
+        let target = self.settings.limiting_magnitude();
+        let step = LIMIT_SPEED * delta_time;
+        self.limit += (target - self.limit).clamp(-step, step);
+    }
+
+    /// Opacity of the sky glow at the bottom edge, fading along with the limit
+    pub fn glow(&self) -> f32 {
+        let amount = (DARK_MAGNITUDE - self.limit) / (DARK_MAGNITUDE - CITY_MAGNITUDE);
+        amount.clamp(0.0, 1.0) * MAX_GLOW
+    }
+}
+
+impl Default for LightPollution {
+    fn default() -> Self {
+        Self::new(LightPollutionSettings {
+            amount: 0.0,
+            color: DEFAULT_COLOR,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dark_sky_shows_the_faintest_stars() {
+        let limit = LightPollutionSettings {
+            amount: 0.0,
+            color: DEFAULT_COLOR,
+        }
+        .limiting_magnitude();
+        assert_eq!(limit_visibility(FAINTEST_MAGNITUDE, limit), 1.0);
+        assert_eq!(limit_visibility(BRIGHTEST_MAGNITUDE, limit), 1.0);
+    }
+
+    #[test]
+    fn city_sky_hides_faint_stars() {
+        let limit = LightPollutionSettings {
+            amount: 1.0,
+            color: DEFAULT_COLOR,
+        }
+        .limiting_magnitude();
+        assert_eq!(limit_visibility(FAINTEST_MAGNITUDE, limit), 0.0);
+        assert_eq!(limit_visibility(limit, limit), 0.5);
+        assert_eq!(limit_visibility(BRIGHTEST_MAGNITUDE, limit), 1.0);
+    }
+
+    #[test]
+    fn limit_moves_gradually() {
+        let mut pollution = LightPollution::default();
+        pollution.set(LightPollutionSettings {
+            amount: 1.0,
+            color: DEFAULT_COLOR,
+        });
+        pollution.update(1.0);
+        assert_eq!(pollution.limit, DARK_MAGNITUDE - LIMIT_SPEED);
+        assert!(pollution.glow() > 0.0 && pollution.glow() < MAX_GLOW);
+        pollution.settle();
+        assert_eq!(pollution.limit, CITY_MAGNITUDE);
+        assert_eq!(pollution.glow(), MAX_GLOW);
+    }
+}
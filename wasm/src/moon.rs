@@ -0,0 +1,112 @@
+use std::f32::consts::TAU;
+
+use crate::{nightsky::utils::hex_to_rgba, system_options::StarSystemOptions};
+
+/// Days from one new moon to the next
+const SYNODIC_MONTH: f64 = 29.530588853;
+/// A new moon, 2000-01-06 18:14 UTC, in milliseconds since the Unix epoch
+const REFERENCE_NEW_MOON: f64 = 947182440000.0;
+const MS_PER_DAY: f64 = 86400000.0;
+/// How quickly the halo fades with distance, per moon radius
+pub const HALO_FALLOFF: f32 = 0.6;
+/// Color used when `moon_color` can't be read
+const DEFAULT_COLOR: [u8; 3] = [244, 241, 232];
+
+/// Position and look of the moon, shared by the GPU shader and the Canvas2D renderer
+#[derive(Debug, Clone)]
+pub struct MoonSettings {
+    /// Center of the disc as fractions of the canvas width and height
+    pub position: [f32; 2],
+    /// Radius of the disc as a fraction of the canvas height
+    pub radius: f32,
+    /// Fraction of the synodic month, 0.0 is new, 0.5 full
+    pub phase: f32,
+    /// sRGB color of the lit side
+    pub color: [u8; 3],
+    /// Brightness of the unlit side relative to the lit side
+    pub earthshine: f32,
+    /// Strength of the halo around a full moon
+    pub halo: f32,
+}
+
+impl MoonSettings {
+    /// `None` when the moon is turned off
+    /// Without `moon_date` the phase is taken from the current date
+    pub fn from_options(options: &StarSystemOptions) -> Option<Self> {
+        if !options.moon {
+            return None;
+        }
+        let color = match hex_to_rgba(&options.moon_color) {
+            Ok([r, g, b, _]) => [r, g, b],
+            Err(e) => {
+                log::warn!("Invalid moon color '{}': {}", options.moon_color, e);
+                DEFAULT_COLOR
+            }
+        };
+        let date = options.moon_date.as_deref().and_then(|date| {
+            let time = js_sys::Date::parse(date);
+            if time.is_nan() {
+                log::warn!("Invalid moon date '{}', using the current date", date);
+                return None;
+            }
+            Some(time)
+        });
+        Some(Self {
+            position: options.moon_position.map(|edge| edge.clamp(0.0, 1.0)),
+            radius: options.moon_size.clamp(0.0, 0.5),
+            phase: phase_at(date.unwrap_or_else(js_sys::Date::now)),
+            color,
+            earthshine: options.moon_earthshine.clamp(0.0, 1.0),
+            halo: options.moon_halo.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Fraction of the disc that is lit, 0.0 at new moon and 1.0 at full moon
+    pub fn illuminated(&self) -> f32 {
+        (1.0 - (TAU * self.phase).cos()) / 2.0
+    }
+
+    /// The halo follows the lit fraction, a thin crescent barely lights the sky
+    pub fn glow(&self) -> f32 {
+        self.halo * self.illuminated()
+    }
+
+    /// How much of the sky's light the moon washes out at a pixel position
+    /// 1.0 on the disc itself, stars fainter than this disappear
+    /// Mirrors `moon_glow` in `star.wgsl`
+    pub fn glow_at(&self, x: f32, y: f32, width: f32, height: f32) -> f32 {
+        let radius = self.radius * height;
+        if radius <= 0.0 {
+            return 0.0;
+        }
+        let dx = x - self.position[0] * width;
+        let dy = y - self.position[1] * height;
+        let distance = (dx * dx + dy * dy).sqrt() / radius;
+        if distance <= 1.0 {
+            return 1.0;
+        }
+        self.glow() * (-(distance - 1.0) * HALO_FALLOFF).exp()
+    }
+}
+
+/// Phase of the moon at a time in milliseconds since the Unix epoch
+/// 0.0 is new, 0.25 first quarter, 0.5 full and 0.75 last quarter
+pub fn phase_at(time: f64) -> f32 {
+    let days = (time - REFERENCE_NEW_MOON) / MS_PER_DAY;
+    (days.rem_euclid(SYNODIC_MONTH) / SYNODIC_MONTH) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phase_follows_the_synodic_month() {
+        let month = SYNODIC_MONTH * MS_PER_DAY;
+        assert_eq!(phase_at(REFERENCE_NEW_MOON), 0.0);
+        assert!((phase_at(REFERENCE_NEW_MOON + month / 2.0) - 0.5).abs() < 1e-6);
+        assert!((phase_at(REFERENCE_NEW_MOON + month * 3.25) - 0.25).abs() < 1e-6);
+        // Before the reference new moon
+        assert!((phase_at(REFERENCE_NEW_MOON - month / 4.0) - 0.75).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,114 @@
+use std::{cell::Cell, rc::Rc};
+
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::MediaQueryList;
+
+const REDUCED_MOTION_QUERY: &str = "(prefers-reduced-motion: reduce)";
+
+/// How much the sky is allowed to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// Stars twinkle and drift
+    Full,
+    /// Stars twinkle in place
+    Reduced,
+    /// A static frame, redrawn only when something changes
+    None,
+}
+
+impl Motion {
+    /// Parse a motion mode from its option name
+    /// "auto" follows the `prefers-reduced-motion` media query
+    /// Unknown names fall back to full motion
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "full" => Motion::Full,
+            "reduced" => Motion::Reduced,
+            "none" => Motion::None,
+            "auto" => Self::preferred(),
+            _ => {
+                log::warn!("Unknown motion '{}', using full", name);
+                Motion::Full
+            }
+        }
+    }
+
+    /// Reduced when the user asked for less motion
+    /// Workers can't query media, so they get full motion
+    fn preferred() -> Self {
+        let reduce = reduced_motion_query().is_some_and(|query| query.matches());
+        Self::for_preference(reduce)
+    }
+
+    fn for_preference(reduce: bool) -> Self {
+        if reduce {
+            Motion::Reduced
+        } else {
+            Motion::Full
+        }
+    }
+
+    /// Multiplier for star drift
+    pub fn drift(&self) -> f32 {
+        match self {
+            Motion::Full => 1.0,
+            Motion::Reduced | Motion::None => 0.0,
+        }
+    }
+
+    /// Whether the sky is drawn once instead of animated
+    pub fn is_static(&self) -> bool {
+        *self == Motion::None
+    }
+}
+
+fn reduced_motion_query() -> Option<MediaQueryList> {
+    web_sys::window()
+        .and_then(|window| window.match_media(REDUCED_MOTION_QUERY).ok())
+        .flatten()
+}
+
+/// Follows `prefers-reduced-motion` through the media query's `change` event
+/// The listener is removed when this is dropped
+pub struct MotionListener {
+    query: MediaQueryList,
+    changed: Rc<Cell<bool>>,
+    callback: Closure<dyn FnMut()>,
+}
+
+impl MotionListener {
+    /// Start listening on the media query
+    /// Fails inside a worker, which can't query media
+    pub fn new() -> Result<Self, String> {
+        let query = reduced_motion_query()
+            .ok_or("No media to watch, the motion preference can't be followed in a worker")?;
+        let changed = Rc::new(Cell::new(false));
+        let callback = {
+            let changed = changed.clone();
+            Closure::<dyn FnMut()>::new(move || changed.set(true))
+        };
+        query
+            .add_event_listener_with_callback("change", callback.as_ref().unchecked_ref())
+            .map_err(|e| format!("{:?}", e))?;
+        Ok(Self {
+            query,
+            changed,
+            callback,
+        })
+    }
+
+    /// The motion the user prefers now, if the preference changed since the last call
+    pub fn take_change(&self) -> Option<Motion> {
+        self.changed
+            .replace(false)
+            .then(|| Motion::for_preference(self.query.matches()))
+    }
+}
+
+impl Drop for MotionListener {
+    fn drop(&mut self) {
+        let _ = self
+            .query
+            .remove_event_listener_with_callback("change", self.callback.as_ref().unchecked_ref());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::aurora::AuroraSettings;
+
+use super::{context::GpuContext, layer::FullscreenLayer, utils::srgb_to_linear};
+
+/// Aurora settings as laid out for the shader
+/// Matches the WGSL struct in `aurora.wgsl`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct AuroraUniforms {
+    color_low: [f32; 4],
+    color_high: [f32; 4],
+    band: [f32; 2],
+    intensity: f32,
+    time: f32,
+}
+
+/// Full screen pass drawing aurora curtains from domain-warped noise
+/// # Info
+/// Drawn first in the star render pass so the stars blend over it
+pub struct AuroraLayer {
+    uniforms: AuroraUniforms,
+    speed: f32,
+    layer: FullscreenLayer,
+}
+
+impl AuroraLayer {
+    pub fn new(
+        gpu: &GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        sky_uniforms: &wgpu::Buffer,
+        settings: &AuroraSettings,
+    ) -> Self {
+        let linear = |[r, g, b]: [u8; 3]| {
+            [
+                srgb_to_linear(r) as f32,
+                srgb_to_linear(g) as f32,
+                srgb_to_linear(b) as f32,
+                1.0,
+            ]
+        };
+        let uniforms = AuroraUniforms {
+            color_low: linear(settings.colors[0]),
+            color_high: linear(settings.colors[1]),
+            band: settings.band,
+            intensity: settings.intensity,
+            time: 0.0,
+        };
+        let layer = FullscreenLayer::new(
+            gpu,
+            config,
+            sky_uniforms,
+            "Aurora",
+            include_str!("aurora.wgsl"),
+            bytemuck::cast_slice(&[uniforms]),
+        );
+        Self {
+            uniforms,
+            speed: settings.speed,
+            layer,
+        }
+    }
+
+    /// Let the curtains flow, `delta_time` is already scaled by the motion setting
+    pub fn update(&mut self, queue: &wgpu::Queue, delta_time: f32) {
+        if delta_time <= 0.0 {
+            return;
+        }
+        self.uniforms.time += delta_time * self.speed;
+        self.layer
+            .write_uniforms(queue, bytemuck::cast_slice(&[self.uniforms]));
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.layer.draw(render_pass);
+    }
+}
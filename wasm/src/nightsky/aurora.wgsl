@@ -0,0 +1,66 @@
+// Aurora curtains drawn behind the stars, appended to `star.wgsl` and `fullscreen.wgsl`
+
+struct Aurora {
+    color_low: vec4<f32>,  // Linear color of the lower edge of the curtains
+    color_high: vec4<f32>, // Linear color of the tops of the curtains
+    band: vec2<f32>,       // Top and bottom of the band as fractions of the height
+    intensity: f32,        // Opacity of the brightest parts
+    time: f32,             // Flow time, already scaled by the speed
+};
+
+@group(0) @binding(1)
+var<uniform> aurora: Aurora;
+
+fn noise_hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(noise_hash(i), noise_hash(i + vec2<f32>(1.0, 0.0)), u.x),
+        mix(noise_hash(i + vec2<f32>(0.0, 1.0)), noise_hash(i + vec2<f32>(1.0, 1.0)), u.x),
+        u.y,
+    );
+}
+
+fn fbm(p: vec2<f32>) -> f32 {
+    var value = 0.0;
+    var amplitude = 0.5;
+    var q = p;
+    for (var i = 0; i < 4; i++) {
+        value += amplitude * value_noise(q);
+        q = q * 2.0 + vec2<f32>(1.7, 9.2);
+        amplitude *= 0.5;
+    }
+    return value;
+}
+
+@fragment
+fn fs_main(input: FullscreenOutput) -> @location(0) vec4<f32> {
+    let t = aurora.time * 0.05;
+    let aspect = uniforms.screen_size.x / uniforms.screen_size.y;
+    let p = vec2<f32>(input.uv.x * aspect * 2.0, input.uv.y);
+
+    // Domain warp, the sample position is pushed around by noise so the curtains fold
+    let warp = vec2<f32>(
+        fbm(p * vec2<f32>(1.5, 0.5) + vec2<f32>(t, 0.0)),
+        fbm(p * vec2<f32>(1.5, 0.5) + vec2<f32>(5.2, -t)),
+    );
+    let q = p + (warp - 0.5) * 1.5;
+
+    // Thin vertical rays, noise stretched along y
+    let folds = fbm(vec2<f32>(q.x * 3.0 + t * 2.0, q.y * 0.3));
+    let rays = pow(1.0 - abs(folds * 2.0 - 1.0), 4.0);
+    let shimmer = 0.6 + 0.4 * fbm(vec2<f32>(q.x * 6.0 - t * 4.0, 0.0));
+
+    // Fade in from the top of the band and out towards its bottom
+    let band = (input.uv.y - aurora.band.x) / max(aurora.band.y - aurora.band.x, 0.001);
+    let envelope = smoothstep(0.0, 0.3, band) * (1.0 - smoothstep(0.7, 1.0, band));
+
+    let alpha = clamp(rays * shimmer * envelope * aurora.intensity, 0.0, 1.0);
+    let color = mix(aurora.color_high.rgb, aurora.color_low.rgb, smoothstep(0.2, 0.9, band));
+    return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,57 @@
+use crate::day_cycle::{DayCycle, DayCycleSettings, SkyLook};
+
+use super::{context::GpuContext, layer::FullscreenLayer, utils::srgb_to_linear};
+
+/// Full screen pass drawing the day/night sky gradient
+/// # Info
+/// Drawn right after the clear and before the moon, it covers the clear color
+pub struct BackdropLayer {
+    pub cycle: DayCycle,
+    layer: FullscreenLayer,
+}
+
+impl BackdropLayer {
+    pub fn new(
+        gpu: &GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        sky_uniforms: &wgpu::Buffer,
+        cycle: DayCycle,
+    ) -> Self {
+        let layer = FullscreenLayer::new(
+            gpu,
+            config,
+            sky_uniforms,
+            "Backdrop",
+            include_str!("backdrop.wgsl"),
+            bytemuck::cast_slice(&gradient(&cycle.look())),
+        );
+        Self { cycle, layer }
+    }
+
+    /// Follow the clock and recolor the sky
+    pub fn update(&mut self, queue: &wgpu::Queue, delta_time: f32) {
+        self.cycle.update(delta_time);
+        let look = self.cycle.look();
+        self.layer
+            .write_uniforms(queue, bytemuck::cast_slice(&gradient(&look)));
+    }
+
+    /// Change the hours of the cycle or the hour it shows
+    pub fn set_settings(&mut self, queue: &wgpu::Queue, settings: DayCycleSettings) {
+        self.cycle = DayCycle::new(settings);
+        self.update(queue, 0.0);
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.layer.draw(render_pass);
+    }
+}
+
+/// Matches `Backdrop` in `backdrop.wgsl`
+/// Blending happens in linear space, so the sRGB colors are converted first
+fn gradient(look: &SkyLook) -> [f32; 8] {
+    let [[r0, g0, b0], [r1, g1, b1]] = look
+        .gradient
+        .map(|color| color.map(|c| srgb_to_linear((c * 255.0).round() as u8) as f32));
+    [r0, g0, b0, 1.0, r1, g1, b1, 1.0]
+}
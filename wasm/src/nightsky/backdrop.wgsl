@@ -0,0 +1,14 @@
+// Sky gradient drawn behind everything else, appended to `star.wgsl` and `fullscreen.wgsl`
+
+struct Backdrop {
+    top: vec4<f32>,    // Linear color overhead
+    bottom: vec4<f32>, // Linear color at the horizon
+};
+
+@group(0) @binding(1)
+var<uniform> backdrop: Backdrop;
+
+@fragment
+fn fs_main(input: FullscreenOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(mix(backdrop.top.rgb, backdrop.bottom.rgb, input.uv.y), 1.0);
+}
@@ -0,0 +1,50 @@
+use crate::clouds::{CloudSettings, Clouds, CLOUD_OPACITY};
+
+use super::{context::GpuContext, layer::FullscreenLayer, utils::srgb_to_linear};
+
+/// Full screen pass drawing the cloud cover over the stars
+/// # Info
+/// The density itself comes from the sky uniforms, which also dim the stars behind it
+pub struct CloudLayer {
+    pub clouds: Clouds,
+    layer: FullscreenLayer,
+}
+
+impl CloudLayer {
+    pub fn new(
+        gpu: &GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        sky_uniforms: &wgpu::Buffer,
+        settings: CloudSettings,
+    ) -> Self {
+        let layer = FullscreenLayer::new(
+            gpu,
+            config,
+            sky_uniforms,
+            "Cloud",
+            include_str!("clouds.wgsl"),
+            bytemuck::cast_slice(&look(&settings)),
+        );
+        Self {
+            clouds: Clouds::new(settings),
+            layer,
+        }
+    }
+
+    /// Change the look of the clouds, they keep drifting from where they are
+    pub fn set_settings(&mut self, queue: &wgpu::Queue, settings: CloudSettings) {
+        self.layer
+            .write_uniforms(queue, bytemuck::cast_slice(&look(&settings)));
+        self.clouds.settings = settings;
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.layer.draw(render_pass);
+    }
+}
+
+/// Matches `CloudLook` in `clouds.wgsl`
+fn look(settings: &CloudSettings) -> [f32; 4] {
+    let [r, g, b] = settings.color.map(|c| srgb_to_linear(c) as f32);
+    [r, g, b, CLOUD_OPACITY]
+}
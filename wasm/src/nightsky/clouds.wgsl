@@ -0,0 +1,14 @@
+// Cloud cover drawn over the stars, appended to `star.wgsl` and `fullscreen.wgsl`
+
+struct CloudLook {
+    color: vec4<f32>, // Linear color (rgb) and opacity of the thickest clouds (a)
+};
+
+@group(0) @binding(1)
+var<uniform> look: CloudLook;
+
+@fragment
+fn fs_main(input: FullscreenOutput) -> @location(0) vec4<f32> {
+    let density = cloud_density(input.uv * uniforms.screen_size);
+    return vec4<f32>(look.color.rgb, density * look.color.a);
+}
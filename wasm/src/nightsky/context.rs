@@ -0,0 +1,124 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    canvas::Canvas,
+    nightsky::{layer::LayerPipeline, pipeline::*, simulation::SimulationPipeline},
+};
+
+type PipelineKey = (wgpu::TextureFormat, StarGeometry, bool);
+type LayerKey = (wgpu::TextureFormat, &'static str);
+
+/// GPU objects that don't depend on a canvas
+/// # Info
+/// A `NightSky` normally has one of these to itself, a `StarContext` hands the
+/// same one to several skies so a page with many canvases uses a single device.
+pub struct GpuContext {
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    /// Shared with pending snapshots, which poll it
+    pub device: Rc<wgpu::Device>,
+    pub queue: wgpu::Queue,
+    pub quad_vertex_buffer: wgpu::Buffer,
+    pub quad_index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    /// Star pipelines by surface format, geometry and whether they are animated
+    pipelines: RefCell<HashMap<PipelineKey, Rc<wgpu::RenderPipeline>>>,
+    /// Full screen layer pipelines by surface format and layer label
+    layer_pipelines: RefCell<HashMap<LayerKey, Rc<LayerPipeline>>>,
+    /// Compute pipeline, only on adapters that support the simulation
+    simulation: Option<Rc<SimulationPipeline>>,
+}
+
+impl GpuContext {
+    /// Pass the surface the context is created for, if any, so the adapter can present to it
+    pub async fn new(
+        instance: wgpu::Instance,
+        surface: Option<&wgpu::Surface<'_>>,
+    ) -> Result<GpuContext, String> {
+        let adapter = request_adapter(&instance, surface)
+            .await
+            .ok_or("No adapter found")?;
+        log::info!("Adapter limits: {:#?}", adapter.limits());
+        let (device, queue) = request_device_and_queue(&adapter)
+            .await
+            .map_err(|e| e.to_string())?;
+        log::info!("Created adapter, device, and queue");
+        let (quad_vertex_buffer, quad_index_buffer, index_count) = create_quad_buffer(&device);
+        let bind_group_layout = create_bind_group_layout(&device);
+        let simulation =
+            supports_compute(&adapter).then(|| Rc::new(SimulationPipeline::new(&device)));
+        Ok(GpuContext {
+            instance,
+            adapter,
+            device: Rc::new(device),
+            queue,
+            quad_vertex_buffer,
+            quad_index_buffer,
+            index_count,
+            bind_group_layout,
+            pipelines: RefCell::new(HashMap::new()),
+            layer_pipelines: RefCell::new(HashMap::new()),
+            simulation,
+        })
+    }
+
+    /// Create a surface for the canvas that the device can draw to
+    pub fn create_surface(&self, canvas: &Canvas) -> Result<wgpu::Surface<'static>, String> {
+        let surface = create_surface(&self.instance, canvas).map_err(|e| e.to_string())?;
+        if !self.adapter.is_surface_supported(&surface) {
+            return Err("The adapter can't present to this canvas".to_string());
+        }
+        Ok(surface)
+    }
+
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        &self.adapter
+    }
+
+    /// The simulation compute pipeline, if the simulation can run on this device
+    pub fn simulation_pipeline(&self) -> Option<Rc<SimulationPipeline>> {
+        self.simulation.clone()
+    }
+
+    /// Get the star pipeline for a surface, creating it the first time it is asked for
+    pub fn render_pipeline(
+        &self,
+        config: &wgpu::SurfaceConfiguration,
+        geometry: StarGeometry,
+        animated: bool,
+    ) -> Rc<wgpu::RenderPipeline> {
+        let key = (render_format(config), geometry, animated);
+        self.pipelines
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| {
+                log::info!("Creating {:?} star pipeline for {:?}", geometry, key.0);
+                Rc::new(create_render_pipeline(
+                    &self.device,
+                    config,
+                    &self.bind_group_layout,
+                    geometry,
+                    animated,
+                ))
+            })
+            .clone()
+    }
+
+    /// Get a full screen layer pipeline, `label` names the kind of layer and its `shader`
+    pub fn layer_pipeline(
+        &self,
+        config: &wgpu::SurfaceConfiguration,
+        label: &'static str,
+        shader: &'static str,
+    ) -> Rc<LayerPipeline> {
+        self.layer_pipelines
+            .borrow_mut()
+            .entry((render_format(config), label))
+            .or_insert_with(|| {
+                log::info!("Creating {} layer pipeline", label);
+                Rc::new(LayerPipeline::new(&self.device, config, label, shader))
+            })
+            .clone()
+    }
+}
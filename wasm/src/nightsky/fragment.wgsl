@@ -1,18 +1,15 @@
 struct FragmentInput {
-    @location(0) brightness: f32,      // Brightness from the vertex shader
-    @location(1) unit_circle_position: vec2<f32>, // Normalized position within the unit circle
-    @location(2) color: vec3<f32>,      // Color from the vertex shader
+    @location(0) brightness: f32,    // Brightness from the vertex shader
+    @location(1) offset: vec2<f32>,  // Pixels from the star center
+    @location(2) color: vec3<f32>,   // Color from the vertex shader
+    @location(3) radius: f32,        // Star radius in pixels
 };
 
 @fragment
 fn main(input: FragmentInput) -> @location(0) vec4<f32> {
-    // Calculate the distance from the center of the unit circle
-    let dist = length(input.unit_circle_position);
+    // Analytic disc, the edge fades out over one pixel
+    let dist = length(input.offset);
+    let alpha = clamp(input.radius - dist + 0.5, 0.0, 1.0);
 
-    // Smoothly fade the edges of the circle
-    let alpha = 1.0 - smoothstep(0.8, 1.0, dist); // Adjust the edge smoothness
-
-    // Set the color to white with brightness controlling alpha
     return vec4<f32>(input.color, input.brightness * alpha);
 }
-
@@ -0,0 +1,16 @@
+// Vertex stage for full screen layers, appended to `star.wgsl` before the layer itself
+
+struct FullscreenOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>, // 0 to 1 from the top left
+};
+
+// A triangle covering the whole screen, no vertex buffer needed
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> FullscreenOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var output: FullscreenOutput;
+    output.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    output.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return output;
+}
@@ -0,0 +1,116 @@
+use super::{pipeline::create_star_buffer, star::Star};
+
+/// Smallest number of stars the buffer has room for
+/// Also keeps the storage binding from ever being empty
+const MIN_CAPACITY: usize = 1024;
+/// The buffer shrinks once it has been at most a quarter full for this many frames
+const SHRINK_DELAY: u32 = 300;
+
+/// GPU buffer of star instances with spare capacity
+/// # Info
+/// The buffer grows geometrically so adding stars usually only uploads the new
+/// stars, and shrinks lazily once the star count has stayed low for a while.
+/// Existing stars are copied on the GPU when the buffer is replaced
+pub struct InstanceBuffer {
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    underused_frames: u32,
+}
+
+impl InstanceBuffer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, stars: &[Star]) -> Self {
+        let capacity = stars.len().next_power_of_two().max(MIN_CAPACITY);
+        let buffer = create_star_buffer(device, capacity);
+        queue.write_buffer(&buffer, 0, bytemuck::cast_slice(stars));
+        Self {
+            buffer,
+            capacity,
+            underused_frames: 0,
+        }
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Make room for `len` stars, keeping the first `used` stars
+    /// The capacity never grows past `max_len`, the most stars the device allows
+    /// Returns true if the buffer was replaced
+    pub fn reserve(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        used: usize,
+        len: usize,
+        max_len: usize,
+    ) -> bool {
+        let len = len.min(max_len);
+        if len <= self.capacity {
+            return false;
+        }
+        let capacity = len.next_power_of_two().max(self.capacity * 2).min(max_len);
+        self.reallocate(device, queue, used, capacity);
+        true
+    }
+
+    /// Release spare capacity once the buffer has been mostly empty for a while
+    /// Call once per frame, returns true if the buffer was replaced
+    pub fn shrink_if_underused(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        len: usize,
+    ) -> bool {
+        if self.capacity <= MIN_CAPACITY || len > self.capacity / 4 {
+            self.underused_frames = 0;
+            return false;
+        }
+        self.underused_frames += 1;
+        if self.underused_frames < SHRINK_DELAY {
+            return false;
+        }
+        let capacity = (self.capacity / 2).max(MIN_CAPACITY);
+        self.reallocate(device, queue, len, capacity);
+        true
+    }
+
+    /// Upload stars starting at the given index
+    pub fn write(&self, queue: &wgpu::Queue, start: usize, stars: &[Star]) {
+        if stars.is_empty() {
+            return;
+        }
+        let offset = (start * std::mem::size_of::<Star>()) as wgpu::BufferAddress;
+        queue.write_buffer(&self.buffer, offset, bytemuck::cast_slice(stars));
+    }
+
+    pub fn size(&self) -> u64 {
+        self.buffer.size()
+    }
+
+    fn reallocate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        used: usize,
+        capacity: usize,
+    ) {
+        log::debug!(
+            "Resizing star buffer from {} to {} stars",
+            self.capacity,
+            capacity
+        );
+        let buffer = create_star_buffer(device, capacity);
+        if used > 0 {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Star Buffer Copy Encoder"),
+            });
+            let size = (used * std::mem::size_of::<Star>()) as wgpu::BufferAddress;
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, size);
+            // Submit straight away so later writes to the new buffer land after the copy
+            queue.submit(std::iter::once(encoder.finish()));
+        }
+        self.buffer = buffer;
+        self.capacity = capacity;
+        self.underused_frames = 0;
+    }
+}
@@ -0,0 +1,158 @@
+use std::rc::Rc;
+
+use wgpu::util::DeviceExt;
+
+use super::{
+    context::GpuContext,
+    pipeline::{render_format, SAMPLE_COUNT},
+};
+
+/// Full screen pass drawn in the star render pass
+/// # Info
+/// Binding 0 is the sky uniforms, so the layer shares the sky time and size,
+/// binding 1 holds the layer's own uniforms
+pub struct FullscreenLayer {
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: Rc<LayerPipeline>,
+}
+
+impl FullscreenLayer {
+    /// `shader` is appended to `star.wgsl` and `fullscreen.wgsl` and needs an `fs_main`
+    pub fn new(
+        gpu: &GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        sky_uniforms: &wgpu::Buffer,
+        label: &'static str,
+        shader: &'static str,
+        uniforms: &[u8],
+    ) -> Self {
+        let pipeline = gpu.layer_pipeline(config, label, shader);
+        let uniform_buffer = gpu
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Uniform Buffer", label)),
+                contents: uniforms,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{} Bind Group", label)),
+            layout: &pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: sky_uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        Self {
+            uniform_buffer,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    pub fn write_uniforms(&self, queue: &wgpu::Queue, uniforms: &[u8]) {
+        queue.write_buffer(&self.uniform_buffer, 0, uniforms);
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Pipeline of one kind of layer, shared by the skies drawing with a `GpuContext`
+pub struct LayerPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl LayerPipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        label: &str,
+        shader: &str,
+    ) -> Self {
+        let uniform_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some(&format!("{} Bind Group Layout", label)),
+            entries: &[uniform_entry(0), uniform_entry(1)],
+        });
+        let pipeline = create_pipeline(device, config, &bind_group_layout, label, shader);
+        Self {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    label: &str,
+    shader: &str,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(&format!("{} Shader", label)),
+        source: wgpu::ShaderSource::Wgsl(
+            [
+                include_str!("star.wgsl"),
+                include_str!("fullscreen.wgsl"),
+                shader,
+            ]
+            .concat()
+            .into(),
+        ),
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(&format!("{} Pipeline Layout", label)),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("{} Pipeline", label)),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: render_format(config),
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: SAMPLE_COUNT,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
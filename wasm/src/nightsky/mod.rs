@@ -1,7 +1,16 @@
-
-pub mod sky;
+pub mod aurora;
+pub mod backdrop;
+pub mod clouds;
+pub mod context;
+pub mod instance_buffer;
+pub mod layer;
+pub mod moon;
 pub mod pipeline;
-pub mod utils;
+pub mod quad;
+pub mod readback;
+pub mod simulation;
+pub mod sky;
+pub mod sky_glow;
 pub mod star;
-pub mod circle;
-pub mod screen;
+pub mod uniforms;
+pub mod utils;
@@ -0,0 +1,48 @@
+use crate::moon::MoonSettings;
+
+use super::{context::GpuContext, layer::FullscreenLayer, utils::srgb_to_linear};
+
+/// Full screen pass drawing the moon and its halo
+/// # Info
+/// Drawn right after the clear so everything else is in front of it,
+/// the geometry and halo strength come from the sky uniforms
+pub struct MoonLayer {
+    pub settings: MoonSettings,
+    layer: FullscreenLayer,
+}
+
+impl MoonLayer {
+    pub fn new(
+        gpu: &GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        sky_uniforms: &wgpu::Buffer,
+        settings: MoonSettings,
+    ) -> Self {
+        let layer = FullscreenLayer::new(
+            gpu,
+            config,
+            sky_uniforms,
+            "Moon",
+            include_str!("moon.wgsl"),
+            bytemuck::cast_slice(&look(&settings)),
+        );
+        Self { settings, layer }
+    }
+
+    /// Change the phase, look or placement of the moon
+    pub fn set_settings(&mut self, queue: &wgpu::Queue, settings: MoonSettings) {
+        self.layer
+            .write_uniforms(queue, bytemuck::cast_slice(&look(&settings)));
+        self.settings = settings;
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.layer.draw(render_pass);
+    }
+}
+
+/// Matches `MoonLook` in `moon.wgsl`, padded to 32 bytes
+fn look(settings: &MoonSettings) -> [f32; 8] {
+    let [r, g, b] = settings.color.map(|c| srgb_to_linear(c) as f32);
+    [r, g, b, 1.0, settings.phase, settings.earthshine, 0.0, 0.0]
+}
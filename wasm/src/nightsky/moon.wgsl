@@ -0,0 +1,37 @@
+// Moon disc and halo drawn behind everything else, appended to `star.wgsl` and `fullscreen.wgsl`
+
+struct MoonLook {
+    color: vec4<f32>, // Linear color of the lit side
+    phase: f32,       // Fraction of the synodic month, 0 is new and 0.5 full
+    earthshine: f32,  // Brightness of the unlit side relative to the lit side
+};
+
+@group(0) @binding(1)
+var<uniform> look: MoonLook;
+
+const TAU: f32 = 6.28318531;
+
+@fragment
+fn fs_main(input: FullscreenOutput) -> @location(0) vec4<f32> {
+    let pixel = input.uv * uniforms.screen_size;
+    let radius = uniforms.moon_radius * uniforms.screen_size.y;
+    let distance = moon_distance(pixel);
+
+    // Halo, the sky brightens towards the moon
+    let halo = moon_glow(pixel) * select(1.0, 0.0, distance <= 1.0);
+
+    // Sphere normal with y up, the sun circles from behind the moon (new)
+    // to the right (first quarter) and in front of it (full)
+    let d = (pixel - uniforms.moon_position * uniforms.screen_size) / radius;
+    let n = vec3<f32>(d.x, -d.y, sqrt(max(1.0 - dot(d, d), 0.0)));
+    let angle = TAU * look.phase;
+    let sun = vec3<f32>(sin(angle), 0.0, -cos(angle));
+    let lit = smoothstep(-0.03, 0.03, dot(n, sun));
+    let shade = mix(look.earthshine, 1.0, lit);
+
+    // Anti-aliased edge a pixel wide
+    let coverage = clamp((1.0 - distance) * radius + 0.5, 0.0, 1.0);
+    let alpha = max(coverage, halo);
+    let color = look.color.rgb * mix(1.0, shade, coverage);
+    return vec4<f32>(color, alpha);
+}
@@ -1,23 +1,35 @@
 use std::{ffi::c_void, ptr::NonNull};
 
-use raw_window_handle::{WebCanvasWindowHandle, WebDisplayHandle};
+use raw_window_handle::{WebCanvasWindowHandle, WebDisplayHandle, WebOffscreenCanvasWindowHandle};
 use wasm_bindgen::JsValue;
-use web_sys::HtmlCanvasElement;
 use wgpu::{util::DeviceExt, SurfaceTargetUnsafe};
 
-use super::{circle::Circle, star::Star};
+use crate::canvas::Canvas;
 
-const SAMPLE_COUNT: u32 = 4;
+use super::{
+    quad::Quad,
+    simulation::{AnimatedStar, WORKGROUP_SIZE},
+    star::Star,
+};
+
+pub const SAMPLE_COUNT: u32 = 4;
 
 /// Create a new wgpu Instance
 /// # Info
 /// The instance is the entry point to the wgpu API.
 /// It is used to create adapters, surfaces, and other resources.
-pub fn create_instance() -> wgpu::Instance {
-    wgpu::Instance::new(wgpu::InstanceDescriptor {
-        backends: wgpu::Backends::GL,
+/// WebGPU is checked for up front, as a canvas can't fall back to
+/// WebGL once it has handed out a WebGPU context
+pub async fn create_instance(backends: wgpu::Backends) -> Result<wgpu::Instance, String> {
+    if backends.contains(wgpu::Backends::BROWSER_WEBGPU)
+        && !wgpu::util::is_browser_webgpu_supported().await
+    {
+        return Err(String::from("WebGPU is not supported by this browser"));
+    }
+    Ok(wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
         ..Default::default()
-    })
+    }))
 }
 
 /// Create a new wgpu Surface
@@ -26,16 +38,27 @@ pub fn create_instance() -> wgpu::Instance {
 /// It is what the renderer will draw to.
 pub fn create_surface(
     instance: &wgpu::Instance,
-    canvas: &HtmlCanvasElement,
+    canvas: &Canvas,
 ) -> Result<wgpu::Surface<'static>, wgpu::CreateSurfaceError> {
-    let value: &JsValue = &canvas;
-    let obj: NonNull<c_void> = NonNull::from(value).cast();
-    let handle = WebCanvasWindowHandle::new(obj);
+    let raw_window_handle = match canvas {
+        Canvas::Html(canvas) => {
+            let value: &JsValue = canvas;
+            let obj: NonNull<c_void> = NonNull::from(value).cast();
+            raw_window_handle::RawWindowHandle::WebCanvas(WebCanvasWindowHandle::new(obj))
+        }
+        Canvas::Offscreen(canvas) => {
+            let value: &JsValue = canvas;
+            let obj: NonNull<c_void> = NonNull::from(value).cast();
+            raw_window_handle::RawWindowHandle::WebOffscreenCanvas(
+                WebOffscreenCanvasWindowHandle::new(obj),
+            )
+        }
+    };
     let display = WebDisplayHandle::new();
 
     let target = SurfaceTargetUnsafe::RawHandle {
         raw_display_handle: raw_window_handle::RawDisplayHandle::Web(display),
-        raw_window_handle: raw_window_handle::RawWindowHandle::WebCanvas(handle),
+        raw_window_handle,
     };
 
     unsafe { instance.create_surface_unsafe(target) }
@@ -44,21 +67,56 @@ pub fn create_surface(
 /// Request an adapter from the instance to fit the surface
 /// # Info
 /// An adapter is the actual graphics processor (GPU) that you are interacting with.
+/// Without a surface any adapter is accepted, which only makes sense on WebGPU
+/// where an adapter isn't tied to a canvas
 pub async fn request_adapter(
     instance: &wgpu::Instance,
-    surface: &wgpu::Surface<'_>,
+    surface: Option<&wgpu::Surface<'_>>,
 ) -> Option<wgpu::Adapter> {
     instance
         .request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(surface),
+            compatible_surface: surface,
             force_fallback_adapter: false,
         })
         .await
 }
 
-pub async fn request_device_and_queue(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
-    let mut limits = wgpu::Limits::downlevel_webgl2_defaults();
+/// Check if the adapter can run the simulation compute shader
+/// The device is only asked for the limits the simulation needs, so an adapter
+/// that passes this check can always create the device
+pub fn supports_compute(adapter: &wgpu::Adapter) -> bool {
+    let adapter_limits = adapter.limits();
+    adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+        && compute_limits(&adapter_limits).check_limits(&adapter_limits)
+}
+
+/// WebGL2 limits raised to what the simulation shader uses
+/// Storage buffer sizes and workgroup counts follow the adapter so large skies still fit
+fn compute_limits(adapter_limits: &wgpu::Limits) -> wgpu::Limits {
+    wgpu::Limits {
+        max_storage_buffers_per_shader_stage: 2,
+        max_storage_buffer_binding_size: adapter_limits.max_storage_buffer_binding_size,
+        max_compute_workgroup_size_x: WORKGROUP_SIZE,
+        max_compute_workgroup_size_y: 1,
+        max_compute_workgroup_size_z: 1,
+        max_compute_invocations_per_workgroup: WORKGROUP_SIZE,
+        max_compute_workgroups_per_dimension: adapter_limits.max_compute_workgroups_per_dimension,
+        ..wgpu::Limits::downlevel_webgl2_defaults()
+    }
+}
+
+pub async fn request_device_and_queue(
+    adapter: &wgpu::Adapter,
+) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    let mut limits = if supports_compute(adapter) {
+        compute_limits(&adapter.limits())
+    } else {
+        wgpu::Limits::downlevel_webgl2_defaults()
+    };
     limits.max_texture_dimension_2d = 4096;
     adapter
         .request_device(
@@ -89,9 +147,9 @@ pub fn create_multisampled_frame(
         mip_level_count: 1,         // No mipmaps needed
         sample_count: SAMPLE_COUNT, // Use the desired sample count (e.g., 4 for 4x MSAA)
         dimension: wgpu::TextureDimension::D2,
-        format: config.format, // Match the surface format
+        format: render_format(config), // Match the surface format
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT, // Must be used as a render attachment
-        view_formats: &[],     // No additional view formats
+        view_formats: &[],             // No additional view formats
     })
 }
 
@@ -101,16 +159,22 @@ pub fn configure_surface(
     height: u32,
     width: u32,
 ) -> wgpu::SurfaceConfiguration {
-    let surface_caps = surface.get_capabilities(&adapter);
+    let surface_caps = surface.get_capabilities(adapter);
     // Shader code in this tutorial assumes an sRGB surface texture. Using a different
     // one will result in all the colors coming out darker. If you want to support non
     // sRGB surfaces, you'll need to account for that when drawing to the frame.
+    // WebGPU canvases don't offer sRGB formats, so render through an sRGB view instead
     let surface_format = surface_caps
         .formats
         .iter()
         .find(|f| f.is_srgb())
         .copied()
         .unwrap_or(surface_caps.formats[0]);
+    let view_formats = if surface_format.is_srgb() {
+        vec![]
+    } else {
+        vec![surface_format.add_srgb_suffix()]
+    };
     wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         format: surface_format,
@@ -118,11 +182,21 @@ pub fn configure_surface(
         height,
         present_mode: surface_caps.present_modes[0],
         alpha_mode: surface_caps.alpha_modes[0],
-        view_formats: vec![],
+        view_formats,
         desired_maximum_frame_latency: 2,
     }
 }
 
+/// The format the stars are rendered in
+/// This is the sRGB view format when the surface itself isn't sRGB
+pub fn render_format(config: &wgpu::SurfaceConfiguration) -> wgpu::TextureFormat {
+    config
+        .view_formats
+        .first()
+        .copied()
+        .unwrap_or(config.format)
+}
+
 pub fn begin_render_pass<'a>(
     encoder: &'a mut wgpu::CommandEncoder,
     view: &'a wgpu::TextureView,
@@ -145,72 +219,78 @@ pub fn begin_render_pass<'a>(
     })
 }
 
-pub fn create_circle_buffer(device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer, u32) {
-    let circle_mesh = Circle::new();
+pub fn create_quad_buffer(device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer, u32) {
+    let quad_mesh = Quad::new();
 
     // Create vertex buffer
     let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Circle Vertex Buffer"),
-        contents: bytemuck::cast_slice(&circle_mesh.vertices),
+        label: Some("Quad Vertex Buffer"),
+        contents: bytemuck::cast_slice(&quad_mesh.vertices),
         usage: wgpu::BufferUsages::VERTEX,
     });
 
     // Create index buffer
     let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Circle Index Buffer"),
-        contents: bytemuck::cast_slice(&circle_mesh.indices),
+        label: Some("Quad Index Buffer"),
+        contents: bytemuck::cast_slice(&quad_mesh.indices),
         usage: wgpu::BufferUsages::INDEX,
     });
 
-    let index_count = circle_mesh.indices.len() as u32;
+    let index_count = quad_mesh.indices.len() as u32;
 
     (vertex_buffer, index_buffer, index_count)
 }
 
-pub fn create_star_buffer(device: &wgpu::Device, stars: &[Star]) -> wgpu::Buffer {
-    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+/// Create a star buffer with room for `capacity` stars
+pub fn create_star_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
         label: Some("Star Buffer"),
-        contents: bytemuck::cast_slice(stars),
+        size: (capacity * std::mem::size_of::<Star>()) as wgpu::BufferAddress,
         usage: wgpu::BufferUsages::VERTEX
             | wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::COPY_DST,
+            | wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
+
+pub fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Uniform Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
     })
 }
 
 pub fn create_bind_group(
     device: &wgpu::Device,
-    screen_buffer: &wgpu::Buffer,
-) -> (wgpu::BindGroupLayout, wgpu::BindGroup) {
-    let screen_size_bind_group_layout =
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Screen Size Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("Screen Size Bind Group"),
-        layout: &screen_size_bind_group_layout,
+    layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Uniform Bind Group"),
+        layout,
         entries: &[wgpu::BindGroupEntry {
             binding: 0,
-            resource: screen_buffer.as_entire_binding(),
+            resource: uniform_buffer.as_entire_binding(),
         }],
-    });
-    (screen_size_bind_group_layout, bind_group)
+    })
 }
 
 pub fn vertex_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
     device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Vertex Shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("vertex.wgsl").into()),
+        source: wgpu::ShaderSource::Wgsl(
+            concat!(include_str!("star.wgsl"), include_str!("vertex.wgsl")).into(),
+        ),
     })
 }
 
@@ -221,10 +301,27 @@ pub fn fragment_shader(device: &wgpu::Device) -> wgpu::ShaderModule {
     })
 }
 
+/// How the stars are drawn by a render pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StarGeometry {
+    /// A quad per star with the disc cut out in the fragment shader
+    Quad,
+    /// A single point per star, for stars smaller than a pixel
+    Point,
+}
+
+/// Create the star render pipeline
+/// When `animated` is set the stars are read from the simulation shader's
+/// animated buffer instead of being animated in the vertex shader
+/// # Vertex buffers
+/// Quads use the quad mesh in slot 0 followed by the stars,
+/// points start with the stars in slot 0
 pub fn create_render_pipeline(
     device: &wgpu::Device,
     config: &wgpu::SurfaceConfiguration,
     bind_group_layout: &wgpu::BindGroupLayout,
+    geometry: StarGeometry,
+    animated: bool,
 ) -> wgpu::RenderPipeline {
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Render Pipeline Layout"),
@@ -234,14 +331,29 @@ pub fn create_render_pipeline(
 
     let vertex_shader = vertex_shader(device);
     let fragment_shader = fragment_shader(device);
+    let mut buffers = match geometry {
+        StarGeometry::Quad => vec![Quad::desc()],
+        StarGeometry::Point => vec![],
+    };
+    if animated {
+        buffers.extend([Star::appearance_desc(), AnimatedStar::desc()]);
+    } else {
+        buffers.push(Star::desc());
+    }
+    let (entry_point, topology) = match (geometry, animated) {
+        (StarGeometry::Quad, false) => ("main", wgpu::PrimitiveTopology::TriangleList),
+        (StarGeometry::Quad, true) => ("main_animated", wgpu::PrimitiveTopology::TriangleList),
+        (StarGeometry::Point, false) => ("main_point", wgpu::PrimitiveTopology::PointList),
+        (StarGeometry::Point, true) => ("main_point_animated", wgpu::PrimitiveTopology::PointList),
+    };
 
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some("Render Pipeline"),
         layout: Some(&render_pipeline_layout),
         vertex: wgpu::VertexState {
             module: &vertex_shader,
-            entry_point: Some("main"),
-            buffers: &[Circle::desc(), Star::desc()],
+            entry_point: Some(entry_point),
+            buffers: &buffers,
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         },
         fragment: Some(wgpu::FragmentState {
@@ -249,14 +361,14 @@ pub fn create_render_pipeline(
             module: &fragment_shader,
             entry_point: Some("main"),
             targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
+                format: render_format(config),
                 blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
             compilation_options: wgpu::PipelineCompilationOptions::default(),
         }),
         primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
+            topology,
             strip_index_format: None,
             front_face: wgpu::FrontFace::Ccw,
             cull_mode: None,
@@ -0,0 +1,28 @@
+/// A unit quad drawn once per star
+/// The fragment shader cuts an anti-aliased disc out of it
+pub struct Quad {
+    pub vertices: Vec<[f32; 2]>, // Corners of the quad
+    pub indices: Vec<u16>,       // Indices for the quad's two triangles
+}
+
+impl Quad {
+    pub fn new() -> Self {
+        Self {
+            vertices: vec![[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        }
+    }
+
+    pub const ATTR: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![
+        // Position
+        0 => Float32x2
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTR,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::{rc::Rc, sync::mpsc};
+
+use crate::{frame_loop::next_task, snapshot::Snapshot};
+
+use super::pipeline::render_format;
+
+/// Offscreen target a frame is rendered into and copied back from for a snapshot
+pub struct Readback {
+    texture: wgpu::Texture,
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    /// Bytes per row in the buffer, rows are padded to `COPY_BYTES_PER_ROW_ALIGNMENT`
+    padded_row: u32,
+    /// The texture stores blue first and is swizzled to RGBA on read
+    bgra: bool,
+}
+
+impl Readback {
+    pub fn new(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Result<Self, String> {
+        let format = render_format(config);
+        let bgra = match format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            other => return Err(format!("Can't take a snapshot of a {:?} surface", other)),
+        };
+        let (width, height) = (config.width, config.height);
+        if width == 0 || height == 0 {
+            return Err(String::from("Can't take a snapshot of an empty canvas"));
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Snapshot Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let padded_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Snapshot Buffer"),
+            size: padded_row as wgpu::BufferAddress * height as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Ok(Self {
+            texture,
+            buffer,
+            width,
+            height,
+            padded_row,
+            bgra,
+        })
+    }
+
+    pub fn view(&self) -> wgpu::TextureView {
+        self.texture
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Copy the rendered texture into the mappable buffer
+    pub fn copy(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &self.buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_row),
+                    rows_per_image: Some(self.height),
+                },
+            },
+            wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Wait for the copy to finish and read the pixels
+    /// The device is polled between tasks since WebGL has no mapping callbacks of its own
+    pub async fn read(self, device: Rc<wgpu::Device>) -> Result<Snapshot, String> {
+        let slice = self.buffer.slice(..);
+        let (sender, mapped) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let result = loop {
+            device.poll(wgpu::Maintain::Poll);
+            if let Ok(result) = mapped.try_recv() {
+                break result;
+            }
+            next_task().await?;
+        };
+        result.map_err(|e| e.to_string())?;
+
+        let row = self.width as usize * 4;
+        let mut pixels = Vec::with_capacity(row * self.height as usize);
+        {
+            let data = slice.get_mapped_range();
+            for padded in data.chunks(self.padded_row as usize) {
+                pixels.extend_from_slice(&padded[..row]);
+            }
+        }
+        self.buffer.unmap();
+        if self.bgra {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Ok(Snapshot {
+            width: self.width,
+            height: self.height,
+            pixels,
+        })
+    }
+}
@@ -0,0 +1,46 @@
+// Matches the layout of `Star` on the CPU
+struct Star {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    color: vec3<f32>,
+    size: f32,
+    birth: f32,
+    period: f32,
+    phase: f32,
+    death: f32,
+    seed: u32,
+    magnitude: f32,
+};
+
+// Matches the layout of `AnimatedStar` on the CPU
+struct AnimatedStar {
+    position: vec2<f32>,
+    brightness: f32,
+    _padding: f32,
+};
+
+@group(0) @binding(1)
+var<storage, read> stars: array<Star>;
+
+@group(0) @binding(2)
+var<storage, read_write> animated: array<AnimatedStar>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= uniforms.star_count) {
+        return;
+    }
+    let star = stars[index];
+    let state = animate_star(
+        star.position,
+        star.velocity,
+        star.birth,
+        star.period,
+        star.phase,
+        star.death,
+        star.seed,
+        star.magnitude,
+    );
+    animated[index] = AnimatedStar(state.xy, state.z, 0.0);
+}
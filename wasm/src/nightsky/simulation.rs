@@ -0,0 +1,214 @@
+use std::rc::Rc;
+
+use super::star::Star;
+
+/// Matches `@workgroup_size` in `simulate.wgsl`
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// Per frame state of a star written by the simulation shader
+/// Matches the WGSL struct in `simulate.wgsl`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct AnimatedStar {
+    pub position: [f32; 2],
+    pub brightness: f32,
+    _padding: f32,
+}
+
+impl AnimatedStar {
+    const ATTR: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+        // Position
+        10 => Float32x2,
+        // Brightness
+        11 => Float32,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTR,
+        }
+    }
+}
+
+/// Compute shader path for animating the stars
+/// # Info
+/// Only available on adapters with compute shaders (WebGPU).
+/// The shader evaluates every star once per frame into the animated buffer,
+/// so the vertex shader doesn't repeat the animation for each quad corner.
+/// Stars are evaluated from the sky time rather than integrated, so moving or
+/// replacing stars on the CPU never leaves stale state on the GPU
+pub struct Simulation {
+    pipeline: Rc<SimulationPipeline>,
+    bind_group: wgpu::BindGroup,
+    animated_buffer: wgpu::Buffer,
+}
+
+/// Compute pipeline shared by the skies drawing with a `GpuContext`
+pub struct SimulationPipeline {
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl SimulationPipeline {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = create_bind_group_layout(device);
+        let pipeline = create_compute_pipeline(device, &bind_group_layout);
+        Self {
+            pipeline,
+            bind_group_layout,
+        }
+    }
+}
+
+impl Simulation {
+    pub fn new(
+        device: &wgpu::Device,
+        pipeline: Rc<SimulationPipeline>,
+        uniform_buffer: &wgpu::Buffer,
+        star_buffer: &wgpu::Buffer,
+    ) -> Self {
+        let animated_buffer = create_animated_buffer(device, star_buffer);
+        let bind_group = create_bind_group(
+            device,
+            &pipeline.bind_group_layout,
+            uniform_buffer,
+            star_buffer,
+            &animated_buffer,
+        );
+        Self {
+            pipeline,
+            bind_group,
+            animated_buffer,
+        }
+    }
+
+    /// Rebind the simulation after the star buffer has been replaced
+    pub fn rebind(
+        &mut self,
+        device: &wgpu::Device,
+        uniform_buffer: &wgpu::Buffer,
+        star_buffer: &wgpu::Buffer,
+    ) {
+        self.animated_buffer = create_animated_buffer(device, star_buffer);
+        self.bind_group = create_bind_group(
+            device,
+            &self.pipeline.bind_group_layout,
+            uniform_buffer,
+            star_buffer,
+            &self.animated_buffer,
+        );
+    }
+
+    /// Record the compute pass that animates the first `star_count` stars
+    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder, star_count: u32) {
+        if star_count == 0 {
+            return;
+        }
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Simulation Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.pipeline.pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(star_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    pub fn animated_buffer(&self) -> &wgpu::Buffer {
+        &self.animated_buffer
+    }
+}
+
+/// Size the animated buffer to hold as many stars as the star buffer
+fn create_animated_buffer(device: &wgpu::Device, star_buffer: &wgpu::Buffer) -> wgpu::Buffer {
+    let stars = star_buffer.size() / std::mem::size_of::<Star>() as u64;
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Animated Star Buffer"),
+        size: (stars.max(1) * std::mem::size_of::<AnimatedStar>() as u64),
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::STORAGE,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Simulation Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            storage(1, true),
+            storage(2, false),
+        ],
+    })
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+    star_buffer: &wgpu::Buffer,
+    animated_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Simulation Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: star_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: animated_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
+
+fn create_compute_pipeline(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+) -> wgpu::ComputePipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Simulation Shader"),
+        source: wgpu::ShaderSource::Wgsl(
+            concat!(include_str!("star.wgsl"), include_str!("simulate.wgsl")).into(),
+        ),
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Simulation Pipeline Layout"),
+        bind_group_layouts: &[bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Simulation Pipeline"),
+        layout: Some(&layout),
+        module: &shader,
+        entry_point: Some("main"),
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        cache: None,
+    })
+}
@@ -1,82 +1,185 @@
-use web_sys::HtmlCanvasElement;
+use std::rc::Rc;
 
 use crate::{
+    aurora::AuroraSettings,
+    canvas::Canvas,
+    clouds::CloudSettings,
+    day_cycle::{DayCycle, DayCycleSettings},
+    light_pollution::{LightPollution, LightPollutionSettings},
+    moon::MoonSettings,
+    motion::Motion,
     nightsky::{
-        pipeline::*, screen::create_screen_size_buffer, star::Star, utils::hex_to_wgpu_color,
+        aurora::AuroraLayer,
+        backdrop::BackdropLayer,
+        clouds::CloudLayer,
+        context::GpuContext,
+        instance_buffer::InstanceBuffer,
+        moon::MoonLayer,
+        pipeline::*,
+        readback::Readback,
+        simulation::Simulation,
+        sky_glow::SkyGlowLayer,
+        star::Star,
+        uniforms::{create_uniform_buffer, Uniforms},
+        utils::hex_to_wgpu_color,
     },
-    star_render::StarRender,
+    saved_state::SkyState,
+    snapshot::SnapshotFuture,
+    star_render::{StarCounts, StarRender},
+    stats::RendererInfo,
+    style::StarStyle,
 };
 
+/// Stars with a smaller radius in pixels are drawn as points
+const POINT_RADIUS: f32 = 0.5;
+
 pub struct NightSky {
-    star_size: f32,
-    _instance: wgpu::Instance,
+    style: StarStyle,
+    /// Device, queue and pipelines, possibly shared with other skies
+    gpu: Rc<GpuContext>,
     surface: wgpu::Surface<'static>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
     surface_config: wgpu::SurfaceConfiguration,
     clear_color: wgpu::Color,
+    /// Live stars come first, followed by dying stars that are fading out
     stars: Vec<Star>,
-    star_buffer: wgpu::Buffer,
-    circle_vertex_buffer: wgpu::Buffer,
-    circle_index_buffer: wgpu::Buffer,
-    index_count: u32,
-    render_pipeline: wgpu::RenderPipeline,
+    live_stars: usize,
+    star_buffer: InstanceBuffer,
+    /// Seconds since the sky was created
+    time: f32,
+    uniform_buffer: wgpu::Buffer,
+    /// Compute shader animation, only available on WebGPU
+    simulation: Option<Simulation>,
+    render_pipeline: Rc<wgpu::RenderPipeline>,
+    /// Draws stars smaller than `POINT_RADIUS` as single points
+    point_pipeline: Option<Rc<wgpu::RenderPipeline>>,
     multisampled_frame: wgpu::Texture,
     bind_group: wgpu::BindGroup,
+    motion: Motion,
+    /// Curtains drawn behind the stars
+    aurora: Option<AuroraLayer>,
+    /// Cloud cover drawn over the stars, also dims the stars behind it
+    clouds: Option<CloudLayer>,
+    /// Moon and halo drawn behind everything, the halo washes out faint stars
+    moon: Option<MoonLayer>,
+    /// Day/night sky gradient, also fades and tints the stars
+    backdrop: Option<BackdropLayer>,
+    /// Limiting magnitude that hides faint stars
+    light: LightPollution,
+    /// Light pollution glow, created the first time there is any pollution
+    sky_glow: Option<SkyGlowLayer>,
 }
 
 impl NightSky {
-    /// Clear color is a hex string
+    /// Create a sky with a GPU device of its own, clear color is a hex string
     pub async fn new(
-        canvas: &HtmlCanvasElement,
+        canvas: &Canvas,
+        backends: wgpu::Backends,
         clear_color: String,
         star_count: u32,
-        star_size: f32,
+        style: StarStyle,
+        point_lod: bool,
     ) -> Result<NightSky, String> {
-        let instance = create_instance();
+        let instance = create_instance(backends).await?;
         let surface = create_surface(&instance, canvas).map_err(|e| e.to_string())?;
         log::info!("Created instance and surface");
-        let adapter = request_adapter(&instance, &surface)
-            .await
-            .ok_or("No adapter found")?;
-        let limits = adapter.limits();
-        log::info!("Adapter limits: {:#?}", limits);
-        let (device, queue) = request_device_and_queue(&adapter)
-            .await
-            .map_err(|e| e.to_string())?;
-        log::info!("Created adapter, device, and queue");
-        let surface_config = configure_surface(&adapter, &surface, canvas.height(), canvas.width());
-        surface.configure(&device, &surface_config);
-        let clear_color = hex_to_wgpu_color(&clear_color).unwrap();
+        let gpu = GpuContext::new(instance, Some(&surface)).await?;
+        Self::with_surface(
+            Rc::new(gpu),
+            surface,
+            canvas,
+            clear_color,
+            star_count,
+            style,
+            point_lod,
+        )
+    }
+
+    /// Create a sky that draws with a device shared with other skies
+    pub fn attach(
+        gpu: Rc<GpuContext>,
+        canvas: &Canvas,
+        clear_color: String,
+        star_count: u32,
+        style: StarStyle,
+        point_lod: bool,
+    ) -> Result<NightSky, String> {
+        let surface = gpu.create_surface(canvas)?;
+        log::info!("Created surface on the shared context");
+        Self::with_surface(
+            gpu,
+            surface,
+            canvas,
+            clear_color,
+            star_count,
+            style,
+            point_lod,
+        )
+    }
+
+    fn with_surface(
+        gpu: Rc<GpuContext>,
+        surface: wgpu::Surface<'static>,
+        canvas: &Canvas,
+        clear_color: String,
+        star_count: u32,
+        style: StarStyle,
+        point_lod: bool,
+    ) -> Result<NightSky, String> {
+        let device = &gpu.device;
+        let surface_config =
+            configure_surface(gpu.adapter(), &surface, canvas.height(), canvas.width());
+        surface.configure(device, &surface_config);
+        let clear_color = hex_to_wgpu_color(&clear_color)?;
         log::info!("Created surface configuration and color: {:?}", clear_color);
-        let stars = Star::generate(star_count as usize, star_size, false);
-        let (circle_vertex_buffer, circle_index_buffer, index_count) =
-            create_circle_buffer(&device);
-        let star_buffer = create_star_buffer(&device, &stars);
-        let multisampled_frame = create_multisampled_frame(&device, &surface_config);
+        let star_count = (star_count as usize).min(max_stars(&gpu));
+        let stars = Star::generate(star_count, &style, false, 0.0);
+        let star_buffer = InstanceBuffer::new(device, &gpu.queue, &stars);
+        let multisampled_frame = create_multisampled_frame(device, &surface_config);
 
-        let screen_buffer =
-            create_screen_size_buffer(&device, canvas.width() as f32, canvas.height() as f32);
-        let (bind_group_layout, bind_group) = create_bind_group(&device, &screen_buffer);
+        let uniforms = Uniforms::new(
+            canvas.width() as f32,
+            canvas.height() as f32,
+            0.0,
+            stars.len() as u32,
+            0.0,
+            Motion::Full.drift(),
+        );
+        let uniform_buffer = create_uniform_buffer(device, uniforms);
+        let bind_group = create_bind_group(device, &gpu.bind_group_layout, &uniform_buffer);
 
-        let render_pipeline = create_render_pipeline(&device, &surface_config, &bind_group_layout);
+        let simulation = gpu.simulation_pipeline().map(|pipeline| {
+            Simulation::new(device, pipeline, &uniform_buffer, star_buffer.buffer())
+        });
+        log::info!("Compute shader simulation: {}", simulation.is_some());
+        let render_pipeline =
+            gpu.render_pipeline(&surface_config, StarGeometry::Quad, simulation.is_some());
+        let point_pipeline = point_lod.then(|| {
+            gpu.render_pipeline(&surface_config, StarGeometry::Point, simulation.is_some())
+        });
 
         Ok(NightSky {
-            star_size,
-            _instance: instance,
+            style,
+            gpu,
             surface,
-            device,
-            queue,
             surface_config,
             clear_color,
+            live_stars: stars.len(),
             stars,
             star_buffer,
-            circle_vertex_buffer,
-            circle_index_buffer,
-            index_count,
+            time: 0.0,
+            uniform_buffer,
+            simulation,
             render_pipeline,
+            point_pipeline,
             multisampled_frame,
             bind_group,
+            motion: Motion::Full,
+            aurora: None,
+            clouds: None,
+            moon: None,
+            backdrop: None,
+            light: LightPollution::default(),
+            sky_glow: None,
         })
     }
 
@@ -85,62 +188,203 @@ impl NightSky {
         self.render();
     }
 
-    pub fn resize(&mut self, canvas: HtmlCanvasElement) -> Result<(), String> {
-        self.surface_config.width = canvas.width();
-        self.surface_config.height = canvas.height();
-        self.surface.configure(&self.device, &self.surface_config);
-        self.multisampled_frame = create_multisampled_frame(&self.device, &self.surface_config);
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), String> {
+        self.surface_config.width = width;
+        self.surface_config.height = height;
+        self.surface
+            .configure(&self.gpu.device, &self.surface_config);
+        self.multisampled_frame = create_multisampled_frame(&self.gpu.device, &self.surface_config);
         Ok(())
     }
 
+    /// Advance the sky time, the stars themselves are animated on the GPU
     fn update(&mut self, delta_time: f32) {
-        self.stars
-            .iter_mut()
-            .for_each(|star| star.update(delta_time));
-        self.queue
-            .write_buffer(&self.star_buffer, 0, bytemuck::cast_slice(&self.stars));
+        self.time += delta_time;
+        if let Some(layer) = self.clouds.as_mut() {
+            layer.clouds.update(delta_time * self.motion.drift());
+        }
+        self.light.update(delta_time);
+        if let Some(sky_glow) = &self.sky_glow {
+            sky_glow.update(&self.gpu.queue, &self.light);
+        }
+        // The day/night cycle follows the clock whatever the motion setting
+        if let Some(backdrop) = self.backdrop.as_mut() {
+            backdrop.update(&self.gpu.queue, delta_time);
+        }
+        self.drop_faded_stars();
+        if self
+            .star_buffer
+            .shrink_if_underused(&self.gpu.device, &self.gpu.queue, self.stars.len())
+        {
+            self.rebind_simulation();
+        }
+        self.write_uniforms();
+        if let Some(aurora) = self.aurora.as_mut() {
+            aurora.update(&self.gpu.queue, delta_time * self.motion.drift());
+        }
+    }
+
+    fn write_uniforms(&self) {
+        let mut uniforms = Uniforms::new(
+            self.surface_config.width as f32,
+            self.surface_config.height as f32,
+            self.time,
+            self.stars.len() as u32,
+            if self.point_pipeline.is_some() {
+                POINT_RADIUS
+            } else {
+                0.0
+            },
+            self.motion.drift(),
+        );
+        if let Some(layer) = &self.clouds {
+            uniforms.cloud_offset = layer.clouds.offset;
+            uniforms.cloud_coverage = layer.clouds.settings.coverage;
+        }
+        if let Some(layer) = &self.moon {
+            uniforms.moon_radius = layer.settings.radius;
+            uniforms.moon_position = layer.settings.position;
+            uniforms.moon_glow = layer.settings.glow();
+        }
+        uniforms.limiting_magnitude = self.light.limit;
+        if let Some(layer) = &self.backdrop {
+            let look = layer.cycle.look();
+            uniforms.star_tint = look.star_tint;
+            uniforms.star_visibility = look.star_visibility;
+        }
+        self.gpu
+            .queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+    }
+
+    /// Drop dying stars that have faded out completely
+    /// The last star takes the freed slot so only that slot is uploaded
+    fn drop_faded_stars(&mut self) {
+        let mut index = self.live_stars;
+        while index < self.stars.len() {
+            if self.time < self.stars[index].faded_at() {
+                index += 1;
+                continue;
+            }
+            self.stars.swap_remove(index);
+            if index < self.stars.len() {
+                self.write_stars(index, 1);
+            }
+        }
+    }
+
+    /// Upload a range of stars to the star buffer
+    fn write_stars(&self, start: usize, count: usize) {
+        self.star_buffer
+            .write(&self.gpu.queue, start, &self.stars[start..start + count]);
+    }
+
+    /// Point the simulation at the current star buffer
+    fn rebind_simulation(&mut self) {
+        if let Some(simulation) = self.simulation.as_mut() {
+            simulation.rebind(
+                &self.gpu.device,
+                &self.uniform_buffer,
+                self.star_buffer.buffer(),
+            );
+        }
     }
 
     fn render(&self) {
         let mut encoder = self.command_encoder();
         let frame = self.surface.get_current_texture().unwrap();
-        let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(render_format(&self.surface_config)),
+            ..Default::default()
+        });
+        self.encode_frame(&mut encoder, &view);
+        self.submit(encoder);
+        frame.present();
+    }
 
+    /// Render the current frame into an offscreen texture and read it back
+    pub fn snapshot(&self) -> Result<SnapshotFuture, String> {
+        let readback = Readback::new(&self.gpu.device, &self.surface_config)?;
+        self.write_uniforms();
+        let mut encoder = self.command_encoder();
+        self.encode_frame(&mut encoder, &readback.view());
+        readback.copy(&mut encoder);
+        self.submit(encoder);
+        Ok(Box::pin(readback.read(self.gpu.device.clone())))
+    }
+
+    /// Record the simulation and the backdrop, moon, aurora, sky glow, star and cloud draws
+    /// resolving into `view`
+    fn encode_frame(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
         // Create the multisampled texture view
         let multisampled_view = self
             .multisampled_frame
             .create_view(&wgpu::TextureViewDescriptor::default());
+        if let Some(simulation) = &self.simulation {
+            simulation.dispatch(encoder, self.stars.len() as u32);
+        }
         // Create the render pass
         {
             let mut render_pass =
-                begin_render_pass(&mut encoder, &view, &multisampled_view, self.clear_color);
+                begin_render_pass(encoder, view, &multisampled_view, self.clear_color);
+            if let Some(backdrop) = &self.backdrop {
+                backdrop.draw(&mut render_pass);
+            }
+            if let Some(moon) = &self.moon {
+                moon.draw(&mut render_pass);
+            }
+            if let Some(aurora) = &self.aurora {
+                aurora.draw(&mut render_pass);
+            }
+            if let Some(sky_glow) = self.sky_glow.as_ref().filter(|_| self.light.glow() > 0.0) {
+                sky_glow.draw(&mut render_pass);
+            }
             if !self.stars.is_empty() {
+                let star_count = self.stars.len() as u32;
+                let stars = self.star_buffer.buffer().slice(..);
+                let animated = self
+                    .simulation
+                    .as_ref()
+                    .map(|simulation| simulation.animated_buffer().slice(..));
+                render_pass.set_bind_group(0, &self.bind_group, &[]);
+
                 render_pass.set_pipeline(&self.render_pipeline);
-                render_pass.set_vertex_buffer(0, self.circle_vertex_buffer.slice(..));
-                render_pass.set_vertex_buffer(1, self.star_buffer.slice(..)); // Instance buffer
+                render_pass.set_vertex_buffer(0, self.gpu.quad_vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, stars); // Instance buffer
+                if let Some(animated) = animated {
+                    render_pass.set_vertex_buffer(2, animated);
+                }
                 render_pass.set_index_buffer(
-                    self.circle_index_buffer.slice(..),
+                    self.gpu.quad_index_buffer.slice(..),
                     wgpu::IndexFormat::Uint16,
                 );
-                render_pass.set_bind_group(0, &self.bind_group, &[]);
-                render_pass.draw_indexed(0..self.index_count, 0, 0..self.stars.len() as u32);
+                render_pass.draw_indexed(0..self.gpu.index_count, 0, 0..star_count);
+
+                if let Some(point_pipeline) = &self.point_pipeline {
+                    render_pass.set_pipeline(point_pipeline);
+                    render_pass.set_vertex_buffer(0, stars);
+                    if let Some(animated) = animated {
+                        render_pass.set_vertex_buffer(1, animated);
+                    }
+                    render_pass.draw(0..1, 0..star_count);
+                }
+            }
+            if let Some(clouds) = &self.clouds {
+                clouds.draw(&mut render_pass);
             }
         }
-        self.submit(encoder);
-        frame.present();
     }
 
     fn command_encoder(&self) -> wgpu::CommandEncoder {
-        self.device
+        self.gpu
+            .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render Encoder"),
             })
     }
 
     fn submit(&self, encoder: wgpu::CommandEncoder) {
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.gpu.queue.submit(std::iter::once(encoder.finish()));
     }
 }
 
@@ -149,23 +393,276 @@ impl StarRender for NightSky {
         NightSky::update_and_render(self, delta_time);
     }
 
-    fn resize(&mut self, canvas: HtmlCanvasElement) {
-        NightSky::resize(self, canvas).unwrap();
+    fn resize(&mut self, width: u32, height: u32) {
+        NightSky::resize(self, width, height).unwrap();
     }
 
     fn add_stars(&mut self, count: u32) {
-        self.stars
-            .extend(Star::generate(count as usize, self.star_size, true));
-        self.star_buffer = create_star_buffer(&self.device, &self.stars);
+        let old_len = self.stars.len();
+        let max_stars = max_stars(&self.gpu);
+        let count = (count as usize).min(max_stars.saturating_sub(old_len));
+        if count == 0 {
+            log::warn!("The star buffer is full at {} stars", old_len);
+            return;
+        }
+        if self.star_buffer.reserve(
+            &self.gpu.device,
+            &self.gpu.queue,
+            old_len,
+            old_len + count,
+            max_stars,
+        ) {
+            self.rebind_simulation();
+        }
+        // A static sky never fades new stars in
+        let dim = !self.motion.is_static();
+        let new_stars = Star::generate(count, &self.style, dim, self.time);
+        insert_live_stars(&mut self.stars, self.live_stars, new_stars);
+        self.write_stars(self.live_stars, old_len + count - self.live_stars);
+        self.live_stars += count;
     }
 
     fn remove_stars(&mut self, count: u32) {
-        self.stars
-            .truncate(self.stars.len().saturating_sub(count as usize));
-        self.star_buffer = create_star_buffer(&self.device, &self.stars);
+        // Moving the boundary turns the newest live stars into dying stars
+        let live_stars = self.live_stars.saturating_sub(count as usize);
+        for star in &mut self.stars[live_stars..self.live_stars] {
+            star.kill(self.time);
+        }
+        self.write_stars(live_stars, self.live_stars - live_stars);
+        self.live_stars = live_stars;
+        if self.motion.is_static() {
+            self.stars.truncate(self.live_stars);
+        }
+    }
+
+    fn get_num_stars(&self) -> StarCounts {
+        StarCounts {
+            live: self.live_stars as u32,
+            dying: (self.stars.len() - self.live_stars) as u32,
+        }
+    }
+
+    fn renderer_info(&self) -> RendererInfo {
+        let adapter_info = self.gpu.adapter().get_info();
+        let backend = match adapter_info.backend {
+            wgpu::Backend::BrowserWebGpu => "webgpu",
+            wgpu::Backend::Gl => "webgl",
+            other => other.to_str(),
+        };
+        RendererInfo {
+            backend: backend.to_string(),
+            adapter_name: adapter_info.name,
+            driver: adapter_info.driver,
+            driver_info: adapter_info.driver_info,
+            surface_format: Some(format!("{:?}", self.surface_config.format)),
+            sample_count: SAMPLE_COUNT,
+            instance_buffer_bytes: self.star_buffer.size()
+                + self
+                    .simulation
+                    .as_ref()
+                    .map_or(0, |simulation| simulation.animated_buffer().size()),
+            compute_simulation: self.simulation.is_some(),
+        }
+    }
+
+    fn snapshot(&mut self) -> SnapshotFuture {
+        match NightSky::snapshot(self) {
+            Ok(snapshot) => snapshot,
+            Err(e) => Box::pin(std::future::ready(Err(e))),
+        }
+    }
+
+    fn export_state(&self) -> Option<SkyState> {
+        Some(SkyState::Gpu {
+            time: self.time,
+            live_stars: self.live_stars,
+            stars: self.stars.clone(),
+        })
+    }
+
+    fn import_state(&mut self, state: SkyState) -> Result<(), String> {
+        let SkyState::Gpu {
+            time,
+            live_stars,
+            mut stars,
+        } = state
+        else {
+            return Err(String::from("The state was saved by the CPU renderer"));
+        };
+        // Like the CPU renderer, keep what fits instead of failing
+        let max_stars = max_stars(&self.gpu);
+        stars.truncate(max_stars);
+        let live_stars = live_stars.min(max_stars);
+        if self
+            .star_buffer
+            .reserve(&self.gpu.device, &self.gpu.queue, 0, stars.len(), max_stars)
+        {
+            self.rebind_simulation();
+        }
+        self.stars = stars;
+        self.live_stars = live_stars;
+        self.time = time;
+        if self.motion.is_static() {
+            self.stars.truncate(self.live_stars);
+        }
+        self.write_stars(0, self.stars.len());
+        Ok(())
+    }
+
+    fn set_motion(&mut self, motion: Motion) {
+        self.motion = motion;
+        // Dying stars would never finish fading in a static sky, nor would the limit move
+        if motion.is_static() {
+            self.stars.truncate(self.live_stars);
+            self.light.settle();
+        }
+    }
+
+    fn set_aurora(&mut self, aurora: Option<AuroraSettings>) {
+        self.aurora = aurora.map(|settings| {
+            AuroraLayer::new(
+                &self.gpu,
+                &self.surface_config,
+                &self.uniform_buffer,
+                &settings,
+            )
+        });
+    }
+
+    fn set_clouds(&mut self, clouds: Option<CloudSettings>) {
+        // An existing layer only gets new uniforms, so a slider doesn't rebuild its pipeline
+        match (&mut self.clouds, clouds) {
+            (Some(layer), Some(settings)) => layer.set_settings(&self.gpu.queue, settings),
+            (layer, clouds) => {
+                *layer = clouds.map(|settings| {
+                    CloudLayer::new(
+                        &self.gpu,
+                        &self.surface_config,
+                        &self.uniform_buffer,
+                        settings,
+                    )
+                })
+            }
+        }
+        self.write_uniforms();
+    }
+
+    fn set_moon(&mut self, moon: Option<MoonSettings>) {
+        match (&mut self.moon, moon) {
+            (Some(layer), Some(settings)) => layer.set_settings(&self.gpu.queue, settings),
+            (layer, moon) => {
+                *layer = moon.map(|settings| {
+                    MoonLayer::new(
+                        &self.gpu,
+                        &self.surface_config,
+                        &self.uniform_buffer,
+                        settings,
+                    )
+                })
+            }
+        }
+        self.write_uniforms();
+    }
+
+    fn set_day_cycle(&mut self, cycle: Option<DayCycleSettings>) {
+        match (&mut self.backdrop, cycle) {
+            (Some(layer), Some(settings)) => layer.set_settings(&self.gpu.queue, settings),
+            (layer, cycle) => {
+                *layer = cycle.map(|settings| {
+                    BackdropLayer::new(
+                        &self.gpu,
+                        &self.surface_config,
+                        &self.uniform_buffer,
+                        DayCycle::new(settings),
+                    )
+                })
+            }
+        }
+        self.write_uniforms();
+    }
+
+    fn set_light_pollution(&mut self, settings: LightPollutionSettings) {
+        self.light.set(settings);
+        if self.motion.is_static() {
+            self.light.settle();
+        }
+        if self.sky_glow.is_none() && self.light.settings.amount > 0.0 {
+            self.sky_glow = Some(SkyGlowLayer::new(
+                &self.gpu,
+                &self.surface_config,
+                &self.uniform_buffer,
+                &self.light,
+            ));
+        }
+        if let Some(sky_glow) = &self.sky_glow {
+            sky_glow.update(&self.gpu.queue, &self.light);
+        }
+        self.write_uniforms();
+    }
+}
+
+/// Most stars a star buffer can hold on the device
+fn max_stars(gpu: &GpuContext) -> usize {
+    let limits = gpu.device.limits();
+    let mut bytes = limits.max_buffer_size;
+    if gpu.simulation_pipeline().is_some() {
+        bytes = bytes.min(limits.max_storage_buffer_binding_size as u64);
+    }
+    let stars = (bytes as usize / std::mem::size_of::<Star>()).max(1);
+    // The buffer capacity is rounded up to a power of two
+    1 << stars.ilog2()
+}
+
+/// Add new live stars after the live stars, moving the dying stars behind them
+fn insert_live_stars(stars: &mut Vec<Star>, live_stars: usize, new_stars: Vec<Star>) {
+    let count = new_stars.len();
+    stars.extend(new_stars);
+    stars[live_stars..].rotate_right(count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nightsky::star::ALIVE;
+
+    fn star(death: f32) -> Star {
+        let mut star: Star = bytemuck::Zeroable::zeroed();
+        star.death = death;
+        star
+    }
+
+    /// Two live stars followed by `dying` stars, then `count` new ones are inserted
+    fn insert(dying: usize, count: usize) -> Vec<Star> {
+        let mut stars = vec![star(ALIVE); 2];
+        stars.extend((0..dying).map(|i| star(i as f32)));
+        insert_live_stars(&mut stars, 2, vec![star(ALIVE); count]);
+        stars
+    }
+
+    fn assert_boundary(stars: &[Star], live: usize, dying: usize) {
+        assert_eq!(stars.len(), live + dying);
+        assert!(stars[..live].iter().all(|star| star.death == ALIVE));
+        let deaths: Vec<f32> = stars[live..].iter().map(|star| star.death).collect();
+        assert_eq!(deaths, (0..dying).map(|i| i as f32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn fewer_new_stars_than_dying() {
+        assert_boundary(&insert(3, 2), 4, 3);
+    }
+
+    #[test]
+    fn as_many_new_stars_as_dying() {
+        assert_boundary(&insert(3, 3), 5, 3);
+    }
+
+    #[test]
+    fn more_new_stars_than_dying() {
+        assert_boundary(&insert(2, 5), 7, 2);
     }
 
-    fn get_num_stars(&self) -> u32 {
-        self.stars.len() as u32
+    #[test]
+    fn no_dying_stars() {
+        assert_boundary(&insert(0, 4), 6, 0);
     }
 }
@@ -0,0 +1,45 @@
+use crate::light_pollution::LightPollution;
+
+use super::{context::GpuContext, layer::FullscreenLayer, utils::srgb_to_linear};
+
+/// Full screen pass drawing the light pollution glow near the bottom edge
+/// # Info
+/// Drawn just before the stars, so it washes out everything behind them
+pub struct SkyGlowLayer {
+    layer: FullscreenLayer,
+}
+
+impl SkyGlowLayer {
+    pub fn new(
+        gpu: &GpuContext,
+        config: &wgpu::SurfaceConfiguration,
+        sky_uniforms: &wgpu::Buffer,
+        light: &LightPollution,
+    ) -> Self {
+        let layer = FullscreenLayer::new(
+            gpu,
+            config,
+            sky_uniforms,
+            "Sky Glow",
+            include_str!("sky_glow.wgsl"),
+            bytemuck::cast_slice(&glow_color(light)),
+        );
+        Self { layer }
+    }
+
+    /// Follow the color and the fading strength of the glow
+    pub fn update(&self, queue: &wgpu::Queue, light: &LightPollution) {
+        self.layer
+            .write_uniforms(queue, bytemuck::cast_slice(&glow_color(light)));
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.layer.draw(render_pass);
+    }
+}
+
+/// Matches `SkyGlow` in `sky_glow.wgsl`
+fn glow_color(light: &LightPollution) -> [f32; 4] {
+    let [r, g, b] = light.settings.color.map(|c| srgb_to_linear(c) as f32);
+    [r, g, b, light.glow()]
+}
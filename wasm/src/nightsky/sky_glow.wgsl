@@ -0,0 +1,18 @@
+// Light pollution glow rising from the bottom edge, appended to `star.wgsl` and `fullscreen.wgsl`
+
+struct SkyGlow {
+    color: vec4<f32>, // Linear color (rgb) and opacity at the bottom edge (a)
+};
+
+@group(0) @binding(1)
+var<uniform> glow: SkyGlow;
+
+// Fraction of the height the glow reaches up to
+const GLOW_HEIGHT: f32 = 0.45;
+
+@fragment
+fn fs_main(input: FullscreenOutput) -> @location(0) vec4<f32> {
+    let rise = clamp((1.0 - input.uv.y) / GLOW_HEIGHT, 0.0, 1.0);
+    let falloff = (1.0 - rise) * (1.0 - rise);
+    return vec4<f32>(glow.color.rgb, glow.color.a * falloff);
+}
@@ -1,33 +1,48 @@
-use crate::utils::random_range;
+use crate::{
+    light_pollution::{magnitude_scale, random_magnitude},
+    style::StarStyle,
+    utils::{random_range, random_u32},
+};
 
 const VEL_MOD: f32 = 0.005;
-const STAR_COLORS: [[f32; 3]; 5] = [
-    [0.8, 0.9, 1.0], // Blue-white
-    [1.0, 1.0, 1.0], // White
-    [1.0, 0.9, 0.8], // Yellow-white
-    [1.0, 0.8, 0.6], // Light orange
-    [1.0, 0.6, 0.4], // Reddish-orange
-];
+/// Death time of a star that is still alive
+pub const ALIVE: f32 = f32::MAX;
 
 /// Star struct
-/// Position: X and Y coordinates
-/// Size: Radius of the star
-/// Brightness: Brightness of the star (0.0 to 1.0)
+/// Holds the immutable description of a star, the shaders animate it
+/// from the sky time so the CPU only touches a star when it spawns or dies
+/// # Animation
+/// A star twinkles from dark to bright and back once per `period`.
+/// Each time it goes dark it respawns at a position hashed from `seed` and
+/// the cycle number, drifting by `velocity` while it is visible.
+/// The layout matches the WGSL struct used by the shaders
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Star {
-    /// X and Y position (x, y) coordinates
+    /// X and Y position (x, y) coordinates at birth
     pub position: [f32; 2],
+    /// Drift in clip space units per second
+    pub velocity: [f32; 2],
+    pub color: [f32; 3], // RGB color of the star
     /// Radius of the star
     pub size: f32,
-    /// Brightness of the star (0.0 to 1.0)
-    pub brightness: f32,
-    pub color: [f32; 3], // RGB color of the star
-    pub fade_speed: f32, // (0.0 to 1.0)
-    pub velocity: [f32; 2],
+    /// Sky time the star was spawned
+    pub birth: f32,
+    /// Seconds for a full twinkle from dark to bright and back
+    pub period: f32,
+    /// Seconds into the first twinkle at birth
+    pub phase: f32,
+    /// Sky time the star started fading out, `ALIVE` until then
+    pub death: f32,
+    /// Seed for the respawn positions
+    pub seed: u32,
+    /// Apparent magnitude, stars fainter than the limiting magnitude are hidden
+    pub magnitude: f32,
+    _padding: [u32; 2],
 }
 
 impl Star {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         x: f32,
         y: f32,
@@ -37,71 +52,142 @@ impl Star {
         x_vel: f32,
         y_vel: f32,
         color: [f32; 3],
+        birth: f32,
     ) -> Self {
+        // Brightness ramps from 0 to 1 and back at fade_speed
+        let period = 2.0 / fade_speed;
         Self {
             position: [x, y],
-            size,
-            brightness,
-            color,
-            fade_speed,
             velocity: [x_vel, y_vel],
+            color,
+            size,
+            birth,
+            period,
+            // Start on the rising edge at the requested brightness
+            phase: brightness.clamp(0.0, 1.0) * period / 2.0,
+            death: ALIVE,
+            seed: random_u32(),
+            magnitude: 0.0,
+            _padding: [0; 2],
         }
     }
 
-    pub fn generate(count: usize, size: f32, dim: bool) -> Vec<Star> {
-        let size = size.clamp(0.1, 4.0);
+    /// Generate stars born at the given sky time
+    /// Dim stars start at zero brightness so they fade in
+    /// Sizes follow the magnitudes, so the many faint stars are the small ones
+    pub fn generate(count: usize, style: &StarStyle, dim: bool, time: f32) -> Vec<Star> {
+        let size = style.size.clamp(0.1, 4.0);
         let brightness = if dim { 0.0 } else { random_range(0.2, 1.0) };
         (0..count)
             .map(|_| {
-                let base_color = STAR_COLORS[random_range(0.0, STAR_COLORS.len() as f32) as usize];
-                let color_variation = |c: f32| (c + random_range(-0.1, 0.1)).clamp(0.0, 1.0);
-                let color = [
-                    color_variation(base_color[0]), // Red
-                    color_variation(base_color[1]), // Green
-                    color_variation(base_color[2]), // Blue
-                ];
-                Star::new(
+                let color = style.random_color();
+                let magnitude = random_magnitude();
+                let star = Star::new(
                     random_range(-1.0, 1.0),
                     random_range(-1.0, 1.0),
-                    random_range(0.001, 0.003) * size,
+                    (0.001 + 0.002 * magnitude_scale(magnitude)) * size,
                     brightness,
-                    random_range(0.08, 0.3),
-                    random_range(-1.0, 1.0) * VEL_MOD,
-                    random_range(-1.0, 1.0) * VEL_MOD,
+                    random_range(0.08, 0.3) * style.twinkle_speed,
+                    random_range(-1.0, 1.0) * VEL_MOD * style.drift,
+                    random_range(-1.0, 1.0) * VEL_MOD * style.drift,
                     color,
-                )
+                    time,
+                );
+                Star { magnitude, ..star }
             })
             .collect()
     }
 
-    pub fn update(&mut self, delta_time: f32) {
-        self.brightness += self.fade_speed * delta_time;
-        if self.brightness >= 1.0 {
-            self.brightness = 1.0;
-            self.fade_speed = -self.fade_speed;
-        } else if self.brightness <= 0.0 {
-            self.brightness = 0.0;
-            self.fade_speed = -self.fade_speed;
-            self.refresh_position();
+    /// Brightness of the star at the given sky time
+    /// Mirrors `animate_star` in `star.wgsl`
+    pub fn brightness_at(&self, time: f32) -> f32 {
+        let twinkle = |t: f32| {
+            let cycle = (t - self.birth + self.phase) / self.period;
+            1.0 - (2.0 * cycle.fract() - 1.0).abs()
+        };
+        if time < self.death {
+            return twinkle(time);
         }
-        self.position[0] += self.velocity[0] * delta_time;
-        self.position[1] += self.velocity[1] * delta_time;
+        let fade_speed = 2.0 / self.period;
+        (twinkle(self.death) - (time - self.death) * fade_speed).max(0.0)
+    }
+
+    /// Position and brightness of the star at the given sky time
+    /// Mirrors `animate_star` in `star.wgsl`, `drift` scales the velocity
+    pub fn animate_at(&self, time: f32, drift: f32) -> ([f32; 2], f32) {
+        let t = time.min(self.death);
+        let fade_time = (time - self.death).max(0.0);
+        let cycle = (t - self.birth + self.phase) / self.period;
+        let cycle_index = cycle.floor();
+        // The first cycle starts at the birth position, later ones respawn at a hashed position
+        let (origin, age) = if cycle_index >= 1.0 {
+            (
+                self.respawn_position(cycle_index as u32),
+                cycle.fract() * self.period,
+            )
+        } else {
+            (self.position, t - self.birth)
+        };
+        let travel = drift * (age + fade_time);
+        let position = [
+            origin[0] + self.velocity[0] * travel,
+            origin[1] + self.velocity[1] * travel,
+        ];
+        (position, self.brightness_at(time))
+    }
+
+    /// Position the star respawns at for a twinkle cycle
+    pub fn respawn_position(&self, cycle_index: u32) -> [f32; 2] {
+        let h = hash(self.seed ^ hash(cycle_index));
+        [unit_float(h) * 2.0 - 1.0, unit_float(hash(h)) * 2.0 - 1.0]
+    }
+
+    /// Start fading the star out at its own speed
+    pub fn kill(&mut self, time: f32) {
+        self.death = time;
     }
 
-    fn refresh_position(&mut self) {
-        self.position[0] = js_sys::Math::random() as f32 * 2.0 - 1.0;
-        self.position[1] = js_sys::Math::random() as f32 * 2.0 - 1.0;
+    /// Check the star can be animated, as a star from a saved state might not be
+    pub fn is_valid(&self) -> bool {
+        let [x, y] = self.position;
+        let [vx, vy] = self.velocity;
+        let [r, g, b] = self.color;
+        let values = [
+            x, y, vx, vy, r, g, b, self.size, self.birth, self.phase, self.death,
+        ];
+        values.iter().all(|value| value.is_finite())
+            && self.magnitude.is_finite()
+            && self.period.is_finite()
+            && self.period > 0.0
     }
 
-    const ATTR: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+    /// Sky time at which a dying star has faded out completely
+    pub fn faded_at(&self) -> f32 {
+        let fade_speed = 2.0 / self.period;
+        self.death + self.brightness_at(self.death) / fade_speed
+    }
+
+    const ATTR: [wgpu::VertexAttribute; 10] = wgpu::vertex_attr_array![
         // Position
         1 => Float32x2,
-        // Size
-        2 => Float32,
-        // Brightness
-        3 => Float32,
+        // Velocity
+        2 => Float32x2,
         // Color
-        4 => Float32x3,
+        3 => Float32x3,
+        // Size
+        4 => Float32,
+        // Birth
+        5 => Float32,
+        // Period
+        6 => Float32,
+        // Phase
+        7 => Float32,
+        // Death
+        8 => Float32,
+        // Seed
+        9 => Uint32,
+        // Magnitude
+        12 => Float32,
     ];
 
     pub fn desc() -> wgpu::VertexBufferLayout<'static> {
@@ -111,4 +197,24 @@ impl Star {
             attributes: &Self::ATTR,
         }
     }
+
+    /// Only the color and size, used when the stars are animated by the simulation shader
+    pub fn appearance_desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTR[2..4],
+        }
+    }
+}
+
+/// PCG hash, matches `hash` in `star.wgsl`
+pub fn hash(value: u32) -> u32 {
+    let state = value.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+pub fn unit_float(value: u32) -> f32 {
+    value as f32 / 4294967295.0
 }
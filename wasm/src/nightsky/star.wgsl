@@ -0,0 +1,157 @@
+// Shared by the vertex, simulation and full screen layer shaders
+// Animation helpers mirror `Star::brightness_at` on the CPU
+
+struct Uniforms {
+    screen_size: vec2<f32>,
+    time: f32,
+    star_count: u32,
+    // Stars with a smaller radius in pixels are drawn by the point pass
+    point_radius: f32,
+    // Multiplier for star drift, 0 keeps stars in place
+    drift: f32,
+    // Pixels the clouds have drifted
+    cloud_offset: vec2<f32>,
+    // Fraction of the sky covered by clouds, 0 turns them off
+    cloud_coverage: f32,
+    // Radius of the moon as a fraction of the height, 0 when there is no moon
+    moon_radius: f32,
+    // Center of the moon as fractions of the width and height
+    moon_position: vec2<f32>,
+    // Strength of the moon's halo
+    moon_glow: f32,
+    // Stars fainter than this magnitude are hidden by light pollution
+    limiting_magnitude: f32,
+    // Multipliers for star colors and brightness, from the day/night cycle
+    star_tint: vec3<f32>,
+    star_visibility: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+// PCG hash, integer arithmetic wraps the same way on WebGL2 and WebGPU
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn unit_float(value: u32) -> f32 {
+    return f32(value) / 4294967295.0;
+}
+
+// Cloud noise, mirrors `Clouds::density` on the CPU
+const CLOUD_SCALE: f32 = 320.0;
+const CLOUD_EDGE: f32 = 0.3;
+
+fn cloud_lattice(cell: vec2<i32>) -> f32 {
+    return unit_float(hash(bitcast<u32>(cell.x) ^ hash(bitcast<u32>(cell.y))));
+}
+
+fn cloud_noise(p: vec2<f32>) -> f32 {
+    let cell = vec2<i32>(floor(p));
+    let f = p - floor(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let top = mix(cloud_lattice(cell), cloud_lattice(cell + vec2<i32>(1, 0)), u.x);
+    let bottom = mix(cloud_lattice(cell + vec2<i32>(0, 1)), cloud_lattice(cell + vec2<i32>(1, 1)), u.x);
+    return mix(top, bottom, u.y);
+}
+
+fn cloud_fbm(start: vec2<f32>) -> f32 {
+    var value = 0.0;
+    var amplitude = 0.5;
+    var p = start;
+    for (var i = 0; i < 4; i++) {
+        value += amplitude * cloud_noise(p);
+        p = p * 2.0 + vec2<f32>(17.0, 31.0);
+        amplitude *= 0.5;
+    }
+    // Four octaves add up to at most 0.9375
+    return value / 0.9375;
+}
+
+// Cloud thickness from 0 to 1 at a pixel position measured from the top left
+fn cloud_density(pixel: vec2<f32>) -> f32 {
+    if (uniforms.cloud_coverage <= 0.0) {
+        return 0.0;
+    }
+    let threshold = 1.0 - uniforms.cloud_coverage;
+    let p = (pixel - uniforms.cloud_offset) / CLOUD_SCALE;
+    return smoothstep(threshold, threshold + CLOUD_EDGE, cloud_fbm(p));
+}
+
+// Moon halo, mirrors `MoonSettings::glow_at` on the CPU
+const HALO_FALLOFF: f32 = 0.6;
+
+// Distance from the moon's center in moon radii
+fn moon_distance(pixel: vec2<f32>) -> f32 {
+    let radius = uniforms.moon_radius * uniforms.screen_size.y;
+    return length(pixel - uniforms.moon_position * uniforms.screen_size) / radius;
+}
+
+// How much of the sky's light the moon washes out, 1 on the disc itself
+fn moon_glow(pixel: vec2<f32>) -> f32 {
+    if (uniforms.moon_radius <= 0.0) {
+        return 0.0;
+    }
+    let distance = moon_distance(pixel);
+    if (distance <= 1.0) {
+        return 1.0;
+    }
+    return uniforms.moon_glow * exp(-(distance - 1.0) * HALO_FALLOFF);
+}
+
+// Light pollution, mirrors `limit_visibility` on the CPU
+const LIMIT_EDGE: f32 = 0.5;
+
+fn limit_visibility(magnitude: f32) -> f32 {
+    return smoothstep(-LIMIT_EDGE, LIMIT_EDGE, uniforms.limiting_magnitude - magnitude);
+}
+
+// Clip space position to pixels from the top left
+fn clip_to_pixel(position: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(position.x + 1.0, 1.0 - position.y) * 0.5 * uniforms.screen_size;
+}
+
+// Triangle wave from dark to bright and back once per period
+fn twinkle(cycle: f32) -> f32 {
+    return 1.0 - abs(2.0 * fract(cycle) - 1.0);
+}
+
+// Position (xy) and brightness (z) of a star at the current sky time,
+// behind the clouds and the moon's halo
+fn animate_star(
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    birth: f32,
+    period: f32,
+    phase: f32,
+    death: f32,
+    seed: u32,
+    magnitude: f32,
+) -> vec3<f32> {
+    // Dying stars stop twinkling and fade out from where they were
+    let time = min(uniforms.time, death);
+    let fade_time = max(uniforms.time - death, 0.0);
+
+    let cycle = (time - birth + phase) / period;
+    let cycle_index = floor(cycle);
+    let fade_speed = 2.0 / period;
+    let brightness = max(twinkle(cycle) - fade_time * fade_speed, 0.0);
+
+    // The first cycle starts at the birth position, later ones respawn at a hashed position
+    var origin = position;
+    var age = time - birth;
+    if (cycle_index >= 1.0) {
+        let h = hash(seed ^ hash(u32(cycle_index)));
+        origin = vec2<f32>(unit_float(h), unit_float(hash(h))) * 2.0 - 1.0;
+        age = (cycle - cycle_index) * period;
+    }
+    let center = origin + velocity * uniforms.drift * (age + fade_time);
+    // Daylight, light pollution and clouds in front of the star dim it,
+    // the moon's halo washes out faint stars
+    let pixel = clip_to_pixel(center);
+    let sky = uniforms.star_visibility * limit_visibility(magnitude);
+    let visible = brightness * sky * (1.0 - cloud_density(pixel));
+    return vec3<f32>(center, max(visible - moon_glow(pixel), 0.0));
+}
@@ -0,0 +1,75 @@
+use wgpu::util::DeviceExt;
+
+use crate::light_pollution::DARK_MAGNITUDE;
+
+/// Per frame values shared by every star
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Uniforms {
+    pub width: f32,
+    pub height: f32,
+    /// Seconds since the sky was created, drives the star animation
+    pub time: f32,
+    /// Number of stars in the star buffer
+    pub star_count: u32,
+    /// Stars with a smaller radius in pixels are drawn as points, 0 disables points
+    pub point_radius: f32,
+    /// Multiplier for star drift, 0 keeps stars in place
+    pub drift: f32,
+    /// Pixels the clouds have drifted
+    pub cloud_offset: [f32; 2],
+    /// Fraction of the sky covered by clouds, 0 turns them off
+    pub cloud_coverage: f32,
+    /// Radius of the moon as a fraction of the height, 0 when there is no moon
+    pub moon_radius: f32,
+    /// Center of the moon as fractions of the width and height
+    pub moon_position: [f32; 2],
+    /// Strength of the moon's halo, which washes out faint stars
+    pub moon_glow: f32,
+    /// Stars fainter than this magnitude are hidden by light pollution
+    pub limiting_magnitude: f32,
+    /// Aligns `star_tint` to 16 bytes like WGSL does
+    _padding: [u32; 2],
+    /// Multiplier for star colors, from the day/night cycle
+    pub star_tint: [f32; 3],
+    /// Multiplier for star brightness, from the day/night cycle
+    pub star_visibility: f32,
+}
+
+impl Uniforms {
+    pub fn new(
+        width: f32,
+        height: f32,
+        time: f32,
+        star_count: u32,
+        point_radius: f32,
+        drift: f32,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            time,
+            star_count,
+            point_radius,
+            drift,
+            cloud_offset: [0.0; 2],
+            cloud_coverage: 0.0,
+            moon_radius: 0.0,
+            moon_position: [0.0; 2],
+            moon_glow: 0.0,
+            limiting_magnitude: DARK_MAGNITUDE,
+            _padding: [0; 2],
+            star_tint: [1.0; 3],
+            star_visibility: 1.0,
+        }
+    }
+}
+
+// Create a uniform buffer for the screen size and time
+pub fn create_uniform_buffer(device: &wgpu::Device, uniforms: Uniforms) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[uniforms]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    })
+}
@@ -1,4 +1,37 @@
 pub fn hex_to_wgpu_color(hex: &str) -> Result<wgpu::Color, String> {
+    let [r, g, b, a] = hex_to_rgba(hex)?;
+
+    Ok(wgpu::Color {
+        r: srgb_to_linear(r),
+        g: srgb_to_linear(g),
+        b: srgb_to_linear(b),
+        a: a as f64 / 255.0, // Alpha remains in the [0, 1] range
+    })
+}
+
+/// Convert an sRGB channel to linear space
+pub fn srgb_to_linear(c: u8) -> f64 {
+    let c = c as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear channel back to an sRGB byte
+pub fn linear_to_srgb(c: f64) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+/// Parse a hex color into sRGB bytes
+pub fn hex_to_rgba(hex: &str) -> Result<[u8; 4], String> {
     // Remove the '#' prefix if present
     let hex = hex.strip_prefix('#').unwrap_or(hex);
 
@@ -17,21 +50,5 @@ pub fn hex_to_wgpu_color(hex: &str) -> Result<wgpu::Color, String> {
         255
     };
 
-    // Convert sRGB to linear space
-    let srgb_to_linear = |c: u8| {
-        let c = c as f64 / 255.0;
-        if c <= 0.04045 {
-            c / 12.92
-        } else {
-            ((c + 0.055) / 1.055).powf(2.4)
-        }
-    };
-
-    Ok(wgpu::Color {
-        r: srgb_to_linear(r),
-        g: srgb_to_linear(g),
-        b: srgb_to_linear(b),
-        a: a as f64 / 255.0, // Alpha remains in the [0, 1] range
-    })
+    Ok([r, g, b, a])
 }
-
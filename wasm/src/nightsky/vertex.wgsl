@@ -1,40 +1,135 @@
-struct VertexInput {
-    @location(0) position: vec2<f32>,         // Circle vertex position
-    @location(1) instance_position: vec2<f32>, // Star instance position
-    @location(2) size: f32,                  // Star instance size
-    @location(3) brightness: f32,           // Star brightness
-    @location(4) color: vec3<f32>,           // Star color
+struct StarInput {
+    @location(1) instance_position: vec2<f32>, // Star position at birth
+    @location(2) velocity: vec2<f32>,          // Star drift per second
+    @location(3) color: vec3<f32>,             // Star color
+    @location(4) size: f32,                    // Star instance size
+    @location(5) birth: f32,                   // Sky time the star spawned
+    @location(6) period: f32,                  // Seconds for a full twinkle
+    @location(7) phase: f32,                   // Seconds into the first twinkle at birth
+    @location(8) death: f32,                   // Sky time the star started fading out
+    @location(9) seed: u32,                    // Seed for respawn positions
+    @location(12) magnitude: f32,              // Apparent magnitude
+};
+
+// Used when the simulation shader has already animated the stars
+struct AnimatedStarInput {
+    @location(3) color: vec3<f32>,              // Star color
+    @location(4) size: f32,                     // Star instance size
+    @location(10) instance_position: vec2<f32>, // Animated star position
+    @location(11) brightness: f32,              // Animated star brightness
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>, // Position in clip space
     @location(0) brightness: f32,               // Brightness passed to fragment shader
-    @location(1) unit_circle_position: vec2<f32>, // Position in the unit circle
-    @location(2) color: vec3<f32>,               // Color passed to fragment shader
+    @location(1) offset: vec2<f32>,             // Pixels from the star center
+    @location(2) color: vec3<f32>,              // Color passed to fragment shader
+    @location(3) radius: f32,                   // Star radius in pixels
 };
 
-@group(0) @binding(0)
-var<uniform> screen_size: vec4<f32>;
+const PI: f32 = 3.14159265;
+// Room around the disc for its anti-aliased edge, in pixels
+const EDGE: f32 = 1.0;
+// Smallest radius a quad is drawn with, smaller stars are dimmed instead
+const MIN_RADIUS: f32 = 0.5;
 
-@vertex
-fn main(input: VertexInput) -> VertexOutput {
+// Star sizes are in clip space units of the canvas width
+fn radius_pixels(size: f32) -> f32 {
+    return size * uniforms.screen_size.x * 0.5;
+}
+
+fn quad_vertex(
+    corner: vec2<f32>,
+    size: f32,
+    center: vec2<f32>,
+    brightness: f32,
+    color: vec3<f32>,
+) -> VertexOutput {
     var output: VertexOutput;
+    let radius = radius_pixels(size);
+
+    // Stars drawn by the point pass collapse to a quad with no area
+    if (radius < uniforms.point_radius) {
+        output.clip_position = vec4<f32>(center, 0.0, 1.0);
+        return output;
+    }
 
-    // Calculate clip-space position
+    // Keep tiny discs a pixel wide and dim them to match their real area
+    let drawn = max(radius, MIN_RADIUS);
+    let offset = corner * (drawn + EDGE);
     output.clip_position = vec4<f32>(
-        input.position * input.size + input.instance_position,
+        center + offset * 2.0 / uniforms.screen_size,
         0.0,
         1.0,
     );
+    output.offset = offset;
+    output.radius = drawn;
+    output.brightness = brightness * (radius * radius) / (drawn * drawn);
+    output.color = color * uniforms.star_tint;
+    return output;
+}
 
-    // Convert position to screen space and normalize
-    let screen_dimensions = screen_size.xy;
-    let screen_position = input.position * input.size;
-    output.unit_circle_position = screen_position / screen_dimensions;
+fn point_vertex(
+    size: f32,
+    center: vec2<f32>,
+    brightness: f32,
+    color: vec3<f32>,
+) -> VertexOutput {
+    var output: VertexOutput;
+    let radius = radius_pixels(size);
 
-    // Pass brightness
-    output.brightness = input.brightness;
-    output.color = input.color;
+    // Stars drawn by the quad pass are moved outside the clip volume
+    if (radius >= uniforms.point_radius) {
+        output.clip_position = vec4<f32>(2.0, 2.0, 0.0, 1.0);
+        return output;
+    }
 
+    // A point covers one pixel, dim it to the area of the star
+    output.clip_position = vec4<f32>(center, 0.0, 1.0);
+    output.offset = vec2<f32>(0.0, 0.0);
+    output.radius = 1.0;
+    output.brightness = brightness * min(PI * radius * radius, 1.0);
+    output.color = color * uniforms.star_tint;
     return output;
 }
+
+fn animate(input: StarInput) -> vec3<f32> {
+    return animate_star(
+        input.instance_position,
+        input.velocity,
+        input.birth,
+        input.period,
+        input.phase,
+        input.death,
+        input.seed,
+        input.magnitude,
+    );
+}
+
+@vertex
+fn main(@location(0) corner: vec2<f32>, input: StarInput) -> VertexOutput {
+    let star = animate(input);
+    return quad_vertex(corner, input.size, star.xy, star.z, input.color);
+}
+
+@vertex
+fn main_animated(@location(0) corner: vec2<f32>, input: AnimatedStarInput) -> VertexOutput {
+    return quad_vertex(
+        corner,
+        input.size,
+        input.instance_position,
+        input.brightness,
+        input.color,
+    );
+}
+
+@vertex
+fn main_point(input: StarInput) -> VertexOutput {
+    let star = animate(input);
+    return point_vertex(input.size, star.xy, star.z, input.color);
+}
+
+@vertex
+fn main_point_animated(input: AnimatedStarInput) -> VertexOutput {
+    return point_vertex(input.size, input.instance_position, input.brightness, input.color);
+}
@@ -0,0 +1,159 @@
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    basic::raster::Framebuffer,
+    light_pollution::{limit_visibility, LightPollutionSettings},
+    motion::Motion,
+    nightsky::{star::Star, utils::hex_to_rgba},
+    style::StarStyle,
+    system_options::StarSystemOptions,
+    utils::with_seed,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+struct SequenceOptions {
+    #[serde(default)]
+    seed: u32,
+    #[serde(default = "default_fps")]
+    fps: f32,
+    #[serde(default = "default_duration_secs")]
+    duration_secs: f32,
+    #[serde(default = "default_looping")]
+    looping: bool,
+}
+
+fn default_fps() -> f32 {
+    30.0
+}
+
+fn default_duration_secs() -> f32 {
+    10.0
+}
+
+fn default_looping() -> bool {
+    true
+}
+
+/// Renders the sky to RGBA frames without a canvas
+/// # Description
+/// Stars are generated from a seed and animated with a fixed timestep,
+/// so the same options always give the same frames
+/// Frames are drawn on the CPU with the same disc model as the GPU renderer
+/// # Looping
+/// When `looping` is set each star twinkles a whole number of times over the
+/// sequence and reuses its respawn positions, so the last frame leads back into the first
+#[wasm_bindgen]
+struct FrameSequence {
+    stars: Vec<Star>,
+    /// Twinkles per loop for each star, empty when not looping
+    cycles: Vec<u32>,
+    frame_time: f32,
+    frame_count: u32,
+    drift: f32,
+    /// Stars fainter than this are hidden, from `light_pollution`
+    limiting_magnitude: f32,
+    background: [u8; 4],
+    framebuffer: Framebuffer,
+}
+
+#[wasm_bindgen]
+impl FrameSequence {
+    /// Create a sequence of frames of the given size in pixels
+    /// # Options
+    /// - `seed` - Seed for the star field (default: 0)
+    /// - `fps` - Frames per second of the sequence (default: 30)
+    /// - `duration_secs` - Length of the sequence in seconds (default: 10)
+    /// - `looping` - Make the sequence loop seamlessly (default: true)
+    /// - `preset`, `star_count`, `stars_per_megapixel`, `star_size`, `palette`,
+    ///   `twinkle_speed`, `drift`, `clear_color`, `motion` and `light_pollution`
+    ///   as for `StarSystem`, without the sky glow
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u32, height: u32, options: js_sys::Object) -> Result<FrameSequence, String> {
+        // The sky itself is described by the same options as a live `StarSystem`
+        let system = StarSystemOptions::from_js(options.clone().into())?;
+        let options: SequenceOptions =
+            serde_wasm_bindgen::from_value(options.into()).map_err(|e| e.to_string())?;
+        if options.fps <= 0.0 || options.duration_secs <= 0.0 {
+            return Err(String::from("fps and duration_secs must be positive"));
+        }
+        let frame_time = 1.0 / options.fps;
+        let frame_count = (options.duration_secs * options.fps).round().max(1.0) as u32;
+        let duration = frame_count as f32 * frame_time;
+
+        let star_count = system.star_count_for(width, height) as usize;
+        let style = StarStyle::from_options(&system);
+        let mut stars = with_seed(options.seed as u64, || {
+            Star::generate(star_count, &style, false, 0.0)
+        });
+        let cycles = if options.looping {
+            stars
+                .iter_mut()
+                .map(|star| {
+                    let cycles = (duration / star.period).round().max(1.0);
+                    star.period = duration / cycles;
+                    cycles as u32
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(FrameSequence {
+            stars,
+            cycles,
+            frame_time,
+            frame_count,
+            drift: Motion::from_name(&system.motion).drift(),
+            limiting_magnitude: LightPollutionSettings::from_options(&system).limiting_magnitude(),
+            background: hex_to_rgba(&system.clear_color)?,
+            framebuffer: Framebuffer::new(width, height),
+        })
+    }
+
+    /// Number of frames in the sequence
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    /// Render a frame of the sequence
+    /// # Returns
+    /// The RGBA pixels, ready for `new ImageData(pixels, width, height)`
+    pub fn render_frame(&mut self, index: u32) -> js_sys::Uint8ClampedArray {
+        let time = (index % self.frame_count) as f32 * self.frame_time;
+        let width = self.framebuffer.width() as f32;
+        let height = self.framebuffer.height() as f32;
+        self.framebuffer.fill(self.background);
+        for (i, star) in self.stars.iter().enumerate() {
+            let (position, brightness) = match self.cycles.get(i) {
+                Some(&cycles) => animate_looping(star, time, self.drift, cycles),
+                None => star.animate_at(time, self.drift),
+            };
+            let brightness = brightness * limit_visibility(star.magnitude, self.limiting_magnitude);
+            if brightness <= 0.0 {
+                continue;
+            }
+            let color = star.color.map(|c| (c * 255.0).round() as u8);
+            self.framebuffer.stamp(
+                ((position[0] + 1.0) * 0.5 * width) as f64,
+                ((1.0 - position[1]) * 0.5 * height) as f64,
+                Framebuffer::sprite_for((star.size * width * 0.5) as f64),
+                color,
+                brightness as f64,
+            );
+        }
+        js_sys::Uint8ClampedArray::from(self.framebuffer.pixels())
+    }
+}
+
+/// Like `Star::animate_at`, but every cycle respawns at a position that repeats each loop
+fn animate_looping(star: &Star, time: f32, drift: f32, cycles: u32) -> ([f32; 2], f32) {
+    let cycle = (time + star.phase) / star.period;
+    let origin = star.respawn_position(cycle.floor() as u32 % cycles);
+    let travel = drift * cycle.fract() * star.period;
+    let position = [
+        origin[0] + star.velocity[0] * travel,
+        origin[1] + star.velocity[1] * travel,
+    ];
+    (position, star.brightness_at(time))
+}
@@ -0,0 +1,144 @@
+/// Easing curve applied to a star count ramp
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Parse an easing from its CSS style name
+    /// Unknown names fall back to linear
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "ease-in" => Easing::EaseIn,
+            "ease-out" => Easing::EaseOut,
+            "ease-in-out" => Easing::EaseInOut,
+            "linear" => Easing::Linear,
+            _ => {
+                log::warn!("Unknown easing '{}', using linear", name);
+                Easing::Linear
+            }
+        }
+    }
+
+    /// Map linear progress (0.0 to 1.0) onto the curve
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// An animated transition of the star count
+pub struct Ramp {
+    start: u32,
+    target: u32,
+    duration: f32,
+    elapsed: f32,
+    easing: Easing,
+    on_complete: Option<js_sys::Function>,
+}
+
+impl Ramp {
+    pub fn new(
+        start: u32,
+        target: u32,
+        duration: f32,
+        easing: Easing,
+        on_complete: Option<js_sys::Function>,
+    ) -> Self {
+        Self {
+            start,
+            target,
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            easing,
+            on_complete,
+        }
+    }
+
+    /// Advance the ramp and get the star count for this point in time
+    pub fn advance(&mut self, delta_time: f32) -> u32 {
+        self.elapsed += delta_time.max(0.0);
+        if self.is_finished() {
+            return self.target;
+        }
+        let progress = self.easing.apply(self.elapsed / self.duration);
+        let start = self.start as f32;
+        let target = self.target as f32;
+        (start + (target - start) * progress).round() as u32
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Fire the completion callback with the final star count
+    /// The callback runs once the current call into the star system has returned,
+    /// so it is free to start another ramp
+    pub fn complete(self) {
+        let Some(callback) = self.on_complete else {
+            return;
+        };
+        let count = wasm_bindgen::JsValue::from(self.target);
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = callback.call1(&wasm_bindgen::JsValue::NULL, &count) {
+                log::error!("Ramp completion callback failed: {:?}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_start_and_end_on_the_range() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(-1.0), 0.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn ramps_to_the_target() {
+        let mut ramp = Ramp::new(100, 200, 2.0, Easing::Linear, None);
+        assert_eq!(ramp.advance(1.0), 150);
+        assert!(!ramp.is_finished());
+        assert_eq!(ramp.advance(5.0), 200);
+        assert!(ramp.is_finished());
+
+        let mut ramp = Ramp::new(200, 100, 1.0, Easing::Linear, None);
+        assert_eq!(ramp.advance(0.25), 175);
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_the_target() {
+        let mut ramp = Ramp::new(0, 500, 0.0, Easing::EaseInOut, None);
+        assert_eq!(ramp.advance(0.0), 500);
+        assert!(ramp.is_finished());
+    }
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{basic::star::BasicStar, nightsky::star::Star};
+
+/// Bumped whenever the saved layout changes, older states are rejected
+pub const STATE_VERSION: u32 = 2;
+
+/// Everything needed to continue a sky after a page load
+#[derive(Serialize, Deserialize)]
+pub struct SavedState {
+    pub version: u32,
+    /// Random number generator state, so new stars continue the same sequence
+    pub rng_state: u64,
+    pub sky: SkyState,
+}
+
+/// The star population of a renderer
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SkyState {
+    /// Stars of the GPU renderer, animated from the sky time
+    Gpu {
+        time: f32,
+        live_stars: usize,
+        #[serde(with = "star_bytes")]
+        stars: Vec<Star>,
+    },
+    /// Stars of the CPU renderer
+    Basic { stars: Vec<BasicStar> },
+}
+
+impl SkyState {
+    /// Reject states with values the renderers can't animate, such as NaN positions
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SkyState::Gpu {
+                time,
+                live_stars,
+                stars,
+            } => {
+                if !time.is_finite() || *time < 0.0 {
+                    return Err(String::from("The state has an invalid sky time"));
+                }
+                if *live_stars > stars.len() {
+                    return Err(String::from("The state has more live stars than stars"));
+                }
+                if !stars.iter().all(Star::is_valid) {
+                    return Err(String::from("The state has an invalid star"));
+                }
+            }
+            SkyState::Basic { stars } => {
+                if !stars.iter().all(BasicStar::is_valid) {
+                    return Err(String::from("The state has an invalid star"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// GPU stars are saved in their buffer layout as base64,
+/// a fraction of the size of a JSON object per star
+mod star_bytes {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::{
+        nightsky::star::Star,
+        utils::{base64_decode, base64_encode},
+    };
+
+    pub fn serialize<S: Serializer>(stars: &[Star], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64_encode(bytemuck::cast_slice(stars)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Star>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let bytes = base64_decode(&encoded).map_err(D::Error::custom)?;
+        let size = std::mem::size_of::<Star>();
+        if bytes.len() % size != 0 {
+            return Err(D::Error::custom("Star data has a partial star"));
+        }
+        // The decoded bytes aren't aligned for `Star`
+        Ok(bytes
+            .chunks_exact(size)
+            .map(bytemuck::pod_read_unaligned)
+            .collect())
+    }
+}
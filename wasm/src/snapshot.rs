@@ -0,0 +1,35 @@
+use std::{future::Future, pin::Pin};
+
+use crate::utils::base64_encode;
+
+/// Pixels of a rendered frame
+pub struct Snapshot {
+    pub width: u32,
+    pub height: u32,
+    /// Tightly packed RGBA rows, top to bottom
+    pub pixels: Vec<u8>,
+}
+
+/// A snapshot that may still be waiting on the GPU
+pub type SnapshotFuture = Pin<Box<dyn Future<Output = Result<Snapshot, String>>>>;
+
+impl Snapshot {
+    /// Encode the pixels as a PNG file
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(|e| e.to_string())?;
+        writer.finish().map_err(|e| e.to_string())?;
+        Ok(png)
+    }
+}
+
+/// A `data:` URL for a PNG file
+pub fn png_data_url(png: &[u8]) -> String {
+    format!("data:image/png;base64,{}", base64_encode(png))
+}
@@ -0,0 +1,42 @@
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::nightsky::{context::GpuContext, pipeline::create_instance};
+
+/// A GPU device shared by several star systems
+/// # Description
+/// Every star system normally creates its own GPU device, so a page with many
+/// star canvases can run into the browser's limit on GPU contexts
+/// Create one `StarContext` and `attach` it to each star system before `init`,
+/// they then draw to their own canvases with the same device, queue and pipelines
+/// # Backends
+/// Sharing needs WebGPU, a WebGL context is tied to the canvas it was created for
+/// Star systems that initialize with another backend, or can't draw with the
+/// shared device, fall back to creating their own
+#[wasm_bindgen]
+pub struct StarContext {
+    gpu: Rc<GpuContext>,
+}
+
+#[wasm_bindgen]
+impl StarContext {
+    /// Request the shared device, fails when WebGPU isn't available
+    pub async fn create() -> Result<StarContext, String> {
+        let instance = create_instance(wgpu::Backends::BROWSER_WEBGPU).await?;
+        let gpu = GpuContext::new(instance, None).await?;
+        log::info!("Created shared star context");
+        Ok(StarContext { gpu: Rc::new(gpu) })
+    }
+
+    /// Name of the adapter the device was created on
+    pub fn adapter_name(&self) -> String {
+        self.gpu.adapter().get_info().name
+    }
+}
+
+impl StarContext {
+    pub(crate) fn gpu(&self) -> Rc<GpuContext> {
+        self.gpu.clone()
+    }
+}
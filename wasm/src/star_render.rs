@@ -1,11 +1,42 @@
-use web_sys::HtmlCanvasElement;
-
+use crate::{
+    aurora::AuroraSettings, clouds::CloudSettings, day_cycle::DayCycleSettings,
+    light_pollution::LightPollutionSettings, moon::MoonSettings, motion::Motion,
+    saved_state::SkyState, snapshot::SnapshotFuture, stats::RendererInfo,
+};
 
+/// Number of stars in a sky
+/// Dying stars have been removed but are still fading out
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StarCounts {
+    pub live: u32,
+    pub dying: u32,
+}
 
 pub trait StarRender {
     fn update_and_render(&mut self, delta_time: f32);
-    fn resize(&mut self, canvas: HtmlCanvasElement);
+    fn resize(&mut self, width: u32, height: u32);
     fn add_stars(&mut self, count: u32);
+    /// Mark stars as dying, they fade out at their own speed before being dropped
     fn remove_stars(&mut self, count: u32);
-    fn get_num_stars(&self) -> u32;
+    fn get_num_stars(&self) -> StarCounts;
+    fn renderer_info(&self) -> RendererInfo;
+    /// Without motion, stars are added at full brightness and removed straight away
+    fn set_motion(&mut self, motion: Motion);
+    /// Draw an aurora behind the stars, `None` turns it off
+    fn set_aurora(&mut self, aurora: Option<AuroraSettings>);
+    /// Drift clouds over the stars, `None` clears the sky
+    fn set_clouds(&mut self, clouds: Option<CloudSettings>);
+    /// Draw the moon behind everything else, `None` removes it
+    fn set_moon(&mut self, moon: Option<MoonSettings>);
+    /// Color the background and fade the stars with the time of day, `None` turns it off
+    fn set_day_cycle(&mut self, cycle: Option<DayCycleSettings>);
+    /// Hide stars past the limiting magnitude, fading them as the limit moves,
+    /// and add a sky glow near the bottom edge
+    fn set_light_pollution(&mut self, settings: LightPollutionSettings);
+    /// Pixels of the current frame, resolves once the GPU has finished drawing it
+    fn snapshot(&mut self) -> SnapshotFuture;
+    /// The star population to save, `None` when there is nothing to save
+    fn export_state(&self) -> Option<SkyState>;
+    /// Replace the stars with a saved population from the same kind of renderer
+    fn import_state(&mut self, state: SkyState) -> Result<(), String>;
 }
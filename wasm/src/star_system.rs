@@ -1,29 +1,95 @@
 use crate::{
+    aurora::AuroraSettings,
+    backend::{Backend, BackendFailure},
     basic::sky::BasicSky,
-    nightsky::sky::NightSky,
+    canvas::Canvas,
+    clouds::CloudSettings,
+    day_cycle::DayCycleSettings,
+    frame_loop::FrameLoop,
+    governor::{Governor, GovernorAction},
+    light_pollution::LightPollutionSettings,
+    moon::MoonSettings,
+    motion::{Motion, MotionListener},
+    nightsky::{context::GpuContext, sky::NightSky},
+    ramp::{Easing, Ramp},
+    saved_state::{SavedState, STATE_VERSION},
+    snapshot::png_data_url,
+    star_context::StarContext,
     star_render::StarRender,
+    stats::{FrameStats, Stats},
+    style::StarStyle,
     system_options::StarSystemOptions,
-    utils::{setup_logger, EmptySky},
+    utils::{random_state, set_random_state, setup_logger, EmptySky},
+    visibility::VisibilityListener,
 };
+use std::{cell::RefCell, future::Future, rc::Rc};
 use wasm_bindgen::prelude::*;
-use web_sys::HtmlCanvasElement;
+use wasm_bindgen_futures::future_to_promise;
+use web_sys::{HtmlCanvasElement, OffscreenCanvas};
 
 /// A star system that renders stars on a canvas
 /// It binds to the input canvas and on the update_and_render call
 /// it will handle rendering the stars
 /// # Rendering
 /// The rendering can be done either with the GPU or with the CPU
-/// By default, the GPU rendering is used
-/// If the use_advanced option is set to false, the CPU rendering is used
+/// The backends are tried in the order of the `backends` option,
+/// by default WebGPU, then WebGL, then the CPU with Canvas2D
+/// If the use_advanced option is set to false, only the CPU rendering is used
 /// GPU rendering is more efficient and can render more stars
+/// # Workers
+/// Created with `with_offscreen_canvas` the star system renders to an
+/// `OffscreenCanvas`, so it can run inside a Web Worker
+/// # Pausing
+/// A paused star system skips its frames, including while the page is hidden
+/// when `pause_when_hidden` is set
+/// Frame delta times are clamped so a long gap doesn't make stars jump or fade out together
+/// # Motion
+/// The `motion` option can keep stars from drifting or draw a static sky
+/// that is only redrawn when the canvas or the stars change
+/// # Animation loop
+/// `start` drives the frames from `requestAnimationFrame` instead of `update_and_render`
+/// # Sharing a GPU device
+/// Star systems attached to the same `StarContext` draw with one WebGPU device
+/// instead of creating a device each
 #[wasm_bindgen]
 struct StarSystem {
-    canvas: HtmlCanvasElement,
+    state: Rc<RefCell<SystemState>>,
+    frame_loop: Option<FrameLoop>,
+}
+
+/// Everything a frame touches, shared with the animation loop
+struct SystemState {
+    canvas: Canvas,
     options: StarSystemOptions,
     sky: Box<dyn StarRender>,
     using_advanced: bool,
+    backend: Option<Backend>,
+    backend_failures: Vec<BackendFailure>,
+    governor: Option<Governor>,
+    ramp: Option<Ramp>,
+    frame_stats: FrameStats,
+    paused: bool,
+    /// Set by pausing or hiding the page so the first frame after resuming is clamped
+    resuming: bool,
+    visibility: Option<VisibilityListener>,
+    motion: Motion,
+    /// Follows `prefers-reduced-motion` while the `motion` option is "auto"
+    motion_listener: Option<MotionListener>,
+    /// A static sky is only drawn when this is set
+    redraw: bool,
+    /// Seconds since a static sky following the clock was last drawn
+    static_clock: f32,
+    /// Shared GPU device set with `attach`
+    context: Option<Rc<GpuContext>>,
 }
 
+/// Longest frame the stars are animated over, longer frames are slowed down
+const MAX_DELTA_TIME: f32 = 0.25;
+/// Delta time of the first frame after a pause, whatever the gap
+const RESUME_DELTA_TIME: f32 = 1.0 / 60.0;
+/// Seconds between redraws of a static sky whose day/night cycle follows the clock
+const STATIC_CLOCK_INTERVAL: f32 = 60.0;
+
 #[wasm_bindgen]
 impl StarSystem {
     /// Create a new star system with the given canvas and options
@@ -39,88 +105,715 @@ impl StarSystem {
     /// - `clear_color` - The color to clear the screen with (default: "#000000")
     /// - `fade_speed` - The speed at which the stars fade (default: 0.001)
     /// - `use_advanced` - Use the advanced GPU rendering system (default: true)
+    /// - `backends` - Backends to try in order of preference, any of "webgpu", "webgl"
+    ///   and "canvas2d" (default: ["webgpu", "webgl", "canvas2d"])
+    /// - `point_lod` - Draw stars smaller than a pixel as single points on the GPU (default: true)
+    /// - `max_basic_stars` - The most stars the CPU renderer will draw (default: 10000)
+    /// - `preset` - Start from a built-in look, one of "deep-space", "city-sky", "twilight",
+    ///   "warp" or "minimal", the other options override its fields (default: unset)
     /// - `star_size` - Modifier for star size between 1.0-4.0 (default: 1.0)
+    /// - `palette` - Hex colors the stars are picked from (default: blue-white to reddish-orange)
+    /// - `twinkle_speed` - Multiplier for how fast stars twinkle (default: 1.0)
+    /// - `drift` - Multiplier for how fast stars drift (default: 1.0)
+    /// - `stars_per_megapixel` - Keep a constant star density instead of a fixed
+    ///   `star_count`, stars are added or removed as the canvas is resized (default: unset)
+    /// - `target_fps` - Enable the performance governor, which grows the star count while
+    ///   frames keep up with this frame rate and sheds stars when they don't (default: unset)
+    /// - `max_star_count` - The ceiling the governor grows the star count towards (default: 20000)
+    /// - `pause_when_hidden` - Pause while the page is hidden, listening for
+    ///   `visibilitychange`, not available in a worker (default: false)
+    /// - `motion` - "full", "reduced" for twinkling without drift, "none" for a static sky,
+    ///   or "auto" to follow `prefers-reduced-motion`, not available in a worker (default: "full")
+    /// - `aurora` - Draw flowing aurora curtains behind the stars (default: false)
+    /// - `aurora_colors` - Hex colors of the lower edge and the tops of the curtains
+    ///   (default: ["#3dff9a", "#9b5cff"])
+    /// - `aurora_band` - Top and bottom of the band the curtains hang in, as fractions
+    ///   of the canvas height (default: [0.1, 0.55])
+    /// - `aurora_intensity` - Opacity of the brightest parts between 0.0-1.0 (default: 0.6)
+    /// - `aurora_speed` - Multiplier for how fast the curtains flow (default: 1.0)
+    /// - `clouds` - Drift clouds over the sky that dim the stars behind them (default: false)
+    /// - `cloud_coverage` - Fraction of the sky covered between 0.0-1.0 (default: 0.4)
+    /// - `cloud_speed` - Multiplier for how fast the clouds drift (default: 1.0)
+    /// - `cloud_direction` - Angle the clouds drift towards in degrees,
+    ///   0 is right and 90 is up (default: 0.0)
+    /// - `cloud_color` - Hex color of the clouds (default: "#2a3040")
+    /// - `moon` - Draw the moon behind the stars, its halo hides faint stars nearby (default: false)
+    /// - `moon_position` - Center of the moon as fractions of the canvas width and height
+    ///   (default: [0.8, 0.2])
+    /// - `moon_size` - Radius of the moon as a fraction of the canvas height (default: 0.05)
+    /// - `moon_date` - Date the phase is computed for, anything `Date.parse` reads
+    ///   (default: the current date)
+    /// - `moon_color` - Hex color of the lit side (default: "#f4f1e8")
+    /// - `moon_earthshine` - Brightness of the unlit side relative to the lit side,
+    ///   0.0 leaves it dark (default: 0.08)
+    /// - `moon_halo` - Strength of the halo around a full moon between 0.0-1.0 (default: 0.35)
+    /// - `day_cycle` - Color the background and fade the stars in and out through dusk,
+    ///   night and dawn, the night sky takes its color from `clear_color` (default: false)
+    /// - `time_of_day` - Local hour between 0.0-24.0 to show, instead of following
+    ///   the viewer's clock (default: unset)
+    /// - `sunrise` - Local hour of the middle of dawn (default: 6.5)
+    /// - `sunset` - Local hour of the middle of dusk (default: 19.5)
+    /// - `twilight_hours` - How long dusk and dawn last in hours (default: 1.5)
+    /// - `light_pollution` - From 0.0 for a dark countryside sky down to magnitude 6.5,
+    ///   to 1.0 for a city center down to magnitude 2, fainter stars are hidden
+    ///   and a glow rises from the bottom edge (default: 0.0)
+    /// - `light_pollution_color` - Hex color of the glow (default: "#d9915c")
     #[wasm_bindgen(constructor)]
     pub fn new(canvas: HtmlCanvasElement, options: js_sys::Object) -> Self {
-        let options: StarSystemOptions =
-            serde_wasm_bindgen::from_value(options.into()).unwrap_or(StarSystemOptions::default());
+        Self::from_canvas(Canvas::Html(canvas), options)
+    }
+
+    /// Create a new star system that renders to an `OffscreenCanvas`
+    /// # Description
+    /// Use this inside a Web Worker with a canvas handed over by
+    /// `transferControlToOffscreen`, the options are the same as for the constructor
+    /// The worker can't see the page, so size changes are passed on with `resize_to`
+    pub fn with_offscreen_canvas(canvas: OffscreenCanvas, options: js_sys::Object) -> Self {
+        Self::from_canvas(Canvas::Offscreen(canvas), options)
+    }
+
+    fn from_canvas(canvas: Canvas, options: js_sys::Object) -> Self {
+        let (options, error) = match StarSystemOptions::from_js(options.into()) {
+            Ok(options) => (options, None),
+            Err(e) => (StarSystemOptions::default(), Some(e)),
+        };
         setup_logger(&options.log_level);
-        Self {
+        if let Some(e) = error {
+            log::warn!("Invalid options, using the defaults: {}", e);
+        }
+        let governor = options
+            .target_fps
+            .map(|fps| Governor::new(fps, options.max_star_count));
+        let pause_when_hidden = options.pause_when_hidden;
+        let motion = Motion::from_name(&options.motion);
+        let mut state = SystemState {
             canvas,
             options,
             sky: Box::new(EmptySky {}),
             using_advanced: false,
+            backend: None,
+            backend_failures: Vec::new(),
+            governor,
+            ramp: None,
+            frame_stats: FrameStats::new(),
+            paused: false,
+            resuming: false,
+            visibility: None,
+            motion,
+            motion_listener: None,
+            redraw: true,
+            static_clock: 0.0,
+            context: None,
+        };
+        state.set_pause_when_hidden(pause_when_hidden);
+        state.follow_motion_preference();
+        Self {
+            state: Rc::new(RefCell::new(state)),
+            frame_loop: None,
         }
     }
 
+    /// Draw with the device of a shared `StarContext`
+    /// Call this before `init`, the context is only used with the WebGPU backend
+    pub fn attach(&mut self, context: &StarContext) {
+        self.state.borrow_mut().context = Some(context.gpu());
+    }
+
     /// Initialize the star system
     /// This needs to be called or update_and_render will not work
     /// Ensure this is awaited on before calling update_and_render
+    /// Each backend in the `backends` option is tried in turn until one succeeds
     pub async fn init(&mut self) {
         log::info!("Initializing star system");
-        if self.options.use_advanced {
-            match self.init_advanced().await {
-                Ok(()) => {
-                    log::info!("Advanced initialization succeeded");
-                    self.using_advanced = true;
+        // The state isn't borrowed across the awaits so a running loop keeps drawing
+        let (canvas, options, context) = {
+            let mut state = self.state.borrow_mut();
+            state.backend_failures.clear();
+            (
+                state.canvas.clone(),
+                state.options.clone(),
+                state.context.clone(),
+            )
+        };
+        let star_count = options.star_count_for(canvas.width(), canvas.height());
+        for backend in Backend::parse_list(&options.backends) {
+            let result = match backend.wgpu_backends() {
+                Some(_) if !options.use_advanced => {
+                    Err(String::from("GPU rendering disabled by use_advanced"))
+                }
+                Some(backends) => {
+                    init_advanced(&canvas, &options, backends, star_count, context.clone()).await
+                }
+                None => init_basic(&canvas, &options, star_count),
+            };
+            let mut state = self.state.borrow_mut();
+            match result {
+                Ok(sky) => {
+                    log::info!("Initialization with {} succeeded", backend.name());
+                    state.sky = sky;
+                    let motion = state.motion;
+                    state.sky.set_motion(motion);
+                    state.sky.set_aurora(AuroraSettings::from_options(&options));
+                    state.sky.set_clouds(CloudSettings::from_options(&options));
+                    state.sky.set_moon(MoonSettings::from_options(&options));
+                    state
+                        .sky
+                        .set_day_cycle(DayCycleSettings::from_options(&options));
+                    state
+                        .sky
+                        .set_light_pollution(LightPollutionSettings::from_options(&options));
+                    state.redraw = true;
+                    state.using_advanced = backend.wgpu_backends().is_some();
+                    state.backend = Some(backend);
                     return;
                 }
-                Err(e) => log::warn!("Advanced initialization failed: {}", e),
+                Err(reason) => {
+                    log::warn!("Initialization with {} failed: {}", backend.name(), reason);
+                    state.backend_failures.push(BackendFailure {
+                        backend: backend.name().to_string(),
+                        reason,
+                    });
+                }
             }
         }
-        self.init_basic();
-        log::info!("Basic initialization succeeded");
+        log::error!("No backend could be initialized, nothing will be rendered");
     }
 
     /// Update and render the stars
+    /// This also advances any running `ramp_to` transition and,
+    /// when the governor is enabled, adjusts the star count
+    /// Nothing is updated or drawn while the system is paused
+    /// Don't call this while the loop from `start` is running
     pub fn update_and_render(&mut self, delta_time: f32) {
-        self.sky.update_and_render(delta_time);
+        self.state.borrow_mut().update_and_render(delta_time);
+    }
+
+    /// Start rendering frames from `requestAnimationFrame`
+    /// # Description
+    /// The delta time is taken from the frame timestamps, so the caller doesn't
+    /// need its own loop, this works in a worker too
+    /// Calling `start` again restarts the loop with the new cap
+    /// The loop keeps running across resizes and skips frames while paused
+    /// # Arguments
+    /// - `max_fps` - Cap the frame rate to save power, frames are skipped to stay
+    ///   under it (default: the display refresh rate)
+    pub fn start(&mut self, max_fps: Option<f32>) {
+        self.stop();
+        let state = self.state.clone();
+        match FrameLoop::start(max_fps, move |delta_time| {
+            state.borrow_mut().update_and_render(delta_time)
+        }) {
+            Ok(frame_loop) => self.frame_loop = Some(frame_loop),
+            Err(e) => log::error!("Failed to start the animation loop: {}", e),
+        }
+    }
+
+    /// Stop the loop started by `start`
+    pub fn stop(&mut self) {
+        self.frame_loop = None;
+    }
+
+    /// Check if the loop started by `start` is running
+    pub fn is_running(&self) -> bool {
+        self.frame_loop.is_some()
+    }
+
+    /// Stop updating and drawing the stars until `resume` is called
+    /// The last frame stays on the canvas
+    pub fn pause(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.paused = true;
+        state.resuming = true;
+    }
+
+    /// Continue after `pause`
+    /// The first frame after resuming is animated as a single short frame
+    pub fn resume(&mut self) {
+        self.state.borrow_mut().paused = false;
+    }
+
+    /// Check if the system is paused, by `pause` or because the page is hidden
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().is_paused()
+    }
+
+    /// Pause automatically while the page is hidden
+    /// This listens for `visibilitychange` on the document, which doesn't exist in a worker
+    pub fn set_pause_when_hidden(&mut self, enabled: bool) {
+        self.state.borrow_mut().set_pause_when_hidden(enabled);
+    }
+
+    /// Change how much the sky moves, see the `motion` option
+    pub fn set_motion(&mut self, motion: String) {
+        let mut state = self.state.borrow_mut();
+        state.motion = Motion::from_name(&motion);
+        state.options.motion = motion;
+        state.follow_motion_preference();
+        let motion = state.motion;
+        state.sky.set_motion(motion);
+        state.redraw = true;
+    }
+
+    /// Turn the aurora on or off, the other `aurora_*` options keep their values
+    pub fn set_aurora(&mut self, enabled: bool) {
+        let mut state = self.state.borrow_mut();
+        state.options.aurora = enabled;
+        let aurora = AuroraSettings::from_options(&state.options);
+        state.sky.set_aurora(aurora);
+        state.redraw = true;
+    }
+
+    /// Change the cloud cover, 0.0 clears the sky
+    /// The other `cloud_*` options keep their values
+    pub fn set_cloud_coverage(&mut self, coverage: f32) {
+        let mut state = self.state.borrow_mut();
+        state.options.clouds = coverage > 0.0;
+        state.options.cloud_coverage = coverage;
+        let clouds = CloudSettings::from_options(&state.options);
+        state.sky.set_clouds(clouds);
+        state.redraw = true;
+    }
+
+    /// Show the moon as it is on a date, anything `Date.parse` reads,
+    /// or as it is today when no date is given
+    pub fn set_moon_date(&mut self, date: Option<String>) {
+        let mut state = self.state.borrow_mut();
+        state.options.moon = true;
+        state.options.moon_date = date;
+        let moon = MoonSettings::from_options(&state.options);
+        state.sky.set_moon(moon);
+        state.redraw = true;
+    }
+
+    /// Follow the day/night cycle, showing a local hour between 0.0-24.0
+    /// or following the viewer's clock when no hour is given
+    pub fn set_time_of_day(&mut self, hour: Option<f32>) {
+        let mut state = self.state.borrow_mut();
+        state.options.day_cycle = true;
+        state.options.time_of_day = hour;
+        let cycle = DayCycleSettings::from_options(&state.options);
+        state.sky.set_day_cycle(cycle);
+        state.redraw = true;
+    }
+
+    /// Change the light pollution between 0.0-1.0, see the `light_pollution` option
+    /// Stars fade in or out over the next frames instead of popping
+    pub fn set_light_pollution(&mut self, amount: f32) {
+        let mut state = self.state.borrow_mut();
+        state.options.light_pollution = amount;
+        let settings = LightPollutionSettings::from_options(&state.options);
+        state.sky.set_light_pollution(settings);
+        state.redraw = true;
     }
 
     /// Resize the star system to new canvas dimensions
+    /// When `stars_per_megapixel` is set the star count follows the new canvas area
     pub fn resize(&mut self, canvas: HtmlCanvasElement) {
-        self.sky.resize(canvas);
+        let (width, height) = (canvas.width(), canvas.height());
+        let mut state = self.state.borrow_mut();
+        state.canvas = Canvas::Html(canvas);
+        state.sky.resize(width, height);
+        state.apply_density();
+        state.redraw = true;
+    }
+
+    /// Resize the canvas and the star system to the given dimensions in pixels
+    /// This is how a worker rendering to an `OffscreenCanvas` follows the page
+    pub fn resize_to(&mut self, width: u32, height: u32) {
+        let mut state = self.state.borrow_mut();
+        state.canvas.set_size(width, height);
+        state.sky.resize(width, height);
+        state.apply_density();
+        state.redraw = true;
+    }
+
+    /// Set the star density in stars per megapixel of canvas area
+    /// Stars are added or removed straight away to match the new density
+    /// Passing `undefined` keeps the current stars and stops following the canvas size
+    pub fn set_stars_per_megapixel(&mut self, density: Option<f32>) {
+        let mut state = self.state.borrow_mut();
+        state.options.stars_per_megapixel = density;
+        state.apply_density();
+        state.redraw = true;
     }
 
     /// Check if the stars are rendered with the GPU
     pub fn is_advanced(&self) -> bool {
-        self.using_advanced
+        self.state.borrow().using_advanced
+    }
+
+    /// Get the name of the backend that initialized, if any
+    pub fn active_backend(&self) -> Option<String> {
+        self.state
+            .borrow()
+            .backend
+            .map(|backend| backend.name().to_string())
+    }
+
+    /// Get the backends that failed to initialize before the active one
+    /// # Returns
+    /// An array of `{ backend, reason }` objects in the order they were tried
+    pub fn backend_failures(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.state.borrow().backend_failures).unwrap_or(JsValue::NULL)
     }
 
     /// Add new stars to the system
     pub fn add_stars(&mut self, count: u32) {
-        self.sky.add_stars(count);
+        let mut state = self.state.borrow_mut();
+        state.sky.add_stars(count);
+        state.redraw = true;
     }
 
     /// Remove stars from the system
+    /// Removed stars fade out at their own speed before they are dropped
     pub fn remove_stars(&mut self, count: u32) {
-        self.sky.remove_stars(count);
+        let mut state = self.state.borrow_mut();
+        state.sky.remove_stars(count);
+        state.redraw = true;
     }
 
+    /// Get the number of live stars
     pub fn get_num_stars(&self) -> u32 {
-        self.sky.get_num_stars()
-    }
-
-    async fn init_advanced(&mut self) -> Result<(), String> {
-        let sky = NightSky::new(
-            &self.canvas,
-            self.options.clear_color.clone(),
-            self.options.star_count,
-            self.options.star_size,
-        )
-        .await?;
-        self.sky = Box::new(sky);
-        return Ok(());
-    }
-
-    fn init_basic(&mut self) {
-        let canvas_id = self.canvas.id();
-        let sky = BasicSky::new(
-            &canvas_id,
-            self.options.fade_speed,
-            self.options.star_count,
-            self.options.star_size.into(),
+        self.state.borrow().sky.get_num_stars().live
+    }
+
+    /// Get the number of removed stars that are still fading out
+    pub fn get_num_dying_stars(&self) -> u32 {
+        self.state.borrow().sky.get_num_stars().dying
+    }
+
+    /// Get frame statistics and renderer diagnostics
+    /// # Returns
+    /// An object with:
+    /// - `fps` - Smoothed frames per second
+    /// - `frame_time_ms` / `p95_frame_time_ms` - Last and 95th percentile frame time
+    /// - `star_count` / `dying_star_count` - Live stars and stars still fading out
+    /// - `renderer` - The active `backend` ("webgpu", "webgl" or "canvas2d"),
+    ///   `adapter_name`, `driver`, `driver_info`, `surface_format`, `sample_count`
+    ///   `instance_buffer_bytes` and whether the stars use the `compute_simulation`
+    pub fn stats(&self) -> JsValue {
+        let state = self.state.borrow();
+        let counts = state.sky.get_num_stars();
+        let stats = Stats {
+            fps: state.frame_stats.fps(),
+            frame_time_ms: state.frame_stats.last_frame_time() * 1000.0,
+            p95_frame_time_ms: state.frame_stats.p95_frame_time() * 1000.0,
+            star_count: counts.live,
+            dying_star_count: counts.dying,
+            renderer: state.sky.renderer_info(),
+        };
+        serde_wasm_bindgen::to_value(&stats).unwrap_or(JsValue::NULL)
+    }
+
+    /// Capture the current frame as a PNG file
+    /// # Description
+    /// On the GPU the frame is drawn again into an offscreen texture and read back,
+    /// so the canvas doesn't need `preserveDrawingBuffer`
+    /// # Returns
+    /// A promise for the PNG bytes as a `Uint8Array`
+    pub fn snapshot(&self) -> js_sys::Promise {
+        let png = self.snapshot_png();
+        future_to_promise(async move {
+            let png = png.await?;
+            Ok(js_sys::Uint8Array::from(png.as_slice()).into())
+        })
+    }
+
+    /// Capture the current frame as a PNG `data:` URL
+    /// # Returns
+    /// A promise for the URL, ready for an `<img>` or a download link
+    pub fn snapshot_data_url(&self) -> js_sys::Promise {
+        let png = self.snapshot_png();
+        future_to_promise(async move { Ok(png_data_url(&png.await?).into()) })
+    }
+
+    /// Start the snapshot without keeping the star system borrowed,
+    /// so it can keep drawing and be changed while the GPU finishes the frame
+    fn snapshot_png(&self) -> impl Future<Output = Result<Vec<u8>, String>> {
+        let snapshot = self.state.borrow_mut().sky.snapshot();
+        async move { snapshot.await?.to_png() }
+    }
+
+    /// Save the sky so a later page can continue it with `import_state`
+    /// # Returns
+    /// A versioned JSON string with every star, including stars still fading out,
+    /// and the random number generator state, ready for `sessionStorage`
+    pub fn export_state(&self) -> Result<String, String> {
+        let sky = self
+            .state
+            .borrow()
+            .sky
+            .export_state()
+            .ok_or("Call init before exporting the state")?;
+        let saved = SavedState {
+            version: STATE_VERSION,
+            rng_state: random_state(),
+            sky,
+        };
+        serde_json::to_string(&saved).map_err(|e| e.to_string())
+    }
+
+    /// Continue a sky saved by `export_state`
+    /// # Info
+    /// The state has to come from the same kind of renderer, GPU stars can't be
+    /// restored on the Canvas2D fallback or the other way around
+    /// States with invalid values are rejected, and the stars it brings become the count
+    /// `stars_per_megapixel` and the governor work from, replacing any running `ramp_to`
+    pub fn import_state(&mut self, state: &str) -> Result<(), String> {
+        let saved: SavedState = serde_json::from_str(state).map_err(|e| e.to_string())?;
+        if saved.version != STATE_VERSION {
+            return Err(format!(
+                "Unsupported state version {}, expected {}",
+                saved.version, STATE_VERSION
+            ));
+        }
+        saved.sky.validate()?;
+        let mut state = self.state.borrow_mut();
+        state.sky.import_state(saved.sky)?;
+        set_random_state(saved.rng_state);
+        state.adopt_star_count();
+        state.redraw = true;
+        Ok(())
+    }
+
+    /// Smoothly change the number of stars over time
+    /// New stars fade in from zero brightness and the count follows the easing curve
+    /// # Arguments
+    /// - `target` - The star count to finish on
+    /// - `duration_secs` - How long the transition takes in seconds
+    /// - `easing` - One of "linear", "ease-in", "ease-out" or "ease-in-out" (default: "linear")
+    /// - `on_complete` - Called with the final star count once the transition finishes
+    /// # Info
+    /// Starting a new ramp replaces the running one without calling its `on_complete`
+    /// The governor holds the star count while a ramp is running
+    pub fn ramp_to(
+        &mut self,
+        target: u32,
+        duration_secs: f32,
+        easing: Option<String>,
+        on_complete: Option<js_sys::Function>,
+    ) {
+        let easing = easing
+            .as_deref()
+            .map(Easing::from_name)
+            .unwrap_or(Easing::Linear);
+        let mut state = self.state.borrow_mut();
+        let ramp = Ramp::new(
+            state.sky.get_num_stars().live,
+            target,
+            duration_secs,
+            easing,
+            on_complete,
         );
-        self.sky = Box::new(sky);
+        state.redraw = true;
+        if ramp.is_finished() {
+            state.set_star_count(target);
+            state.ramp = None;
+            ramp.complete();
+            return;
+        }
+        state.ramp = Some(ramp);
+    }
+
+    /// Check if a `ramp_to` transition is running
+    pub fn is_ramping(&self) -> bool {
+        self.state.borrow().ramp.is_some()
+    }
+}
+
+impl SystemState {
+    fn update_and_render(&mut self, delta_time: f32) {
+        if let Some(motion) = self
+            .motion_listener
+            .as_ref()
+            .and_then(MotionListener::take_change)
+        {
+            self.motion = motion;
+            self.sky.set_motion(motion);
+            self.redraw = true;
+        }
+        if self.is_paused() {
+            self.resuming = true;
+            return;
+        }
+        if self.motion.is_static() {
+            self.update_static(delta_time);
+            return;
+        }
+        let resuming = std::mem::take(&mut self.resuming)
+            || self
+                .visibility
+                .as_ref()
+                .is_some_and(VisibilityListener::take_was_hidden);
+        // The gap before a resume frame says nothing about how fast frames are drawn
+        let delta_time = if resuming {
+            delta_time.min(RESUME_DELTA_TIME)
+        } else {
+            self.frame_stats.record(delta_time);
+            delta_time.min(MAX_DELTA_TIME)
+        };
+        self.sky.update_and_render(delta_time);
+        if let Some(mut ramp) = self.ramp.take() {
+            let count = ramp.advance(delta_time);
+            self.set_star_count(count);
+            if ramp.is_finished() {
+                ramp.complete();
+            } else {
+                self.ramp = Some(ramp);
+            }
+            return;
+        }
+        if let Some(governor) = self.governor.as_mut().filter(|_| !resuming) {
+            match governor.update(delta_time, self.sky.get_num_stars()) {
+                GovernorAction::Add(count) => self.sky.add_stars(count),
+                GovernorAction::Remove(count) => self.sky.remove_stars(count),
+                GovernorAction::Hold => {}
+            }
+        }
+    }
+
+    /// Draw a single frame when something changed, finishing any ramp straight away
+    fn update_static(&mut self, delta_time: f32) {
+        if let Some(mut ramp) = self.ramp.take() {
+            let count = ramp.advance(f32::MAX);
+            self.set_star_count(count);
+            ramp.complete();
+        }
+        // The time of day still moves on without motion
+        if self.options.day_cycle && self.options.time_of_day.is_none() {
+            self.static_clock += delta_time;
+            if self.static_clock >= STATIC_CLOCK_INTERVAL {
+                self.static_clock = 0.0;
+                self.redraw = true;
+            }
+        }
+        if std::mem::take(&mut self.redraw) {
+            self.sky.update_and_render(0.0);
+        }
     }
+
+    fn is_paused(&self) -> bool {
+        self.paused
+            || self
+                .visibility
+                .as_ref()
+                .is_some_and(VisibilityListener::is_hidden)
+    }
+
+    fn set_pause_when_hidden(&mut self, enabled: bool) {
+        self.options.pause_when_hidden = enabled;
+        if !enabled {
+            self.visibility = None;
+            return;
+        }
+        if self.visibility.is_some() {
+            return;
+        }
+        match VisibilityListener::new() {
+            Ok(listener) => self.visibility = Some(listener),
+            Err(e) => log::warn!("Can't pause when hidden: {}", e),
+        }
+    }
+
+    /// Listen for changes to `prefers-reduced-motion` while the motion is "auto"
+    fn follow_motion_preference(&mut self) {
+        if self.options.motion != "auto" {
+            self.motion_listener = None;
+            return;
+        }
+        if self.motion_listener.is_some() {
+            return;
+        }
+        match MotionListener::new() {
+            Ok(listener) => self.motion_listener = Some(listener),
+            Err(e) => log::warn!("Can't follow the motion preference: {}", e),
+        }
+    }
+
+    fn target_star_count(&self) -> u32 {
+        self.options
+            .star_count_for(self.canvas.width(), self.canvas.height())
+    }
+
+    /// Add or remove stars so the count matches `stars_per_megapixel`
+    /// The governor owns the star count when it is enabled
+    fn apply_density(&mut self) {
+        if self.options.stars_per_megapixel.is_none() || self.governor.is_some() {
+            return;
+        }
+        self.set_star_count(self.target_star_count());
+    }
+
+    /// Continue from the current star count after it was replaced by `import_state`
+    /// A running ramp is dropped and the density and governor start over from the new count
+    fn adopt_star_count(&mut self) {
+        let live = self.sky.get_num_stars().live;
+        self.ramp = None;
+        self.options.star_count = live;
+        let megapixels = self.canvas.width() as f64 * self.canvas.height() as f64 / 1_000_000.0;
+        if self.options.stars_per_megapixel.is_some() && megapixels > 0.0 {
+            self.options.stars_per_megapixel = Some((live as f64 / megapixels) as f32);
+        }
+        self.governor = self
+            .options
+            .target_fps
+            .map(|fps| Governor::new(fps, self.options.max_star_count));
+    }
+
+    /// Add or remove stars to reach the given count
+    fn set_star_count(&mut self, target: u32) {
+        let current = self.sky.get_num_stars().live;
+        if target > current {
+            self.sky.add_stars(target - current);
+        } else if target < current {
+            self.sky.remove_stars(current - target);
+        }
+    }
+}
+
+async fn init_advanced(
+    canvas: &Canvas,
+    options: &StarSystemOptions,
+    backends: wgpu::Backends,
+    star_count: u32,
+    context: Option<Rc<GpuContext>>,
+) -> Result<Box<dyn StarRender>, String> {
+    if let Some(gpu) = context.filter(|_| backends.contains(wgpu::Backends::BROWSER_WEBGPU)) {
+        match NightSky::attach(
+            gpu,
+            canvas,
+            options.clear_color.clone(),
+            star_count,
+            StarStyle::from_options(options),
+            options.point_lod,
+        ) {
+            Ok(sky) => return Ok(Box::new(sky)),
+            Err(e) => log::warn!("Couldn't use the shared context, creating a device: {}", e),
+        }
+    }
+    let sky = NightSky::new(
+        canvas,
+        backends,
+        options.clear_color.clone(),
+        star_count,
+        StarStyle::from_options(options),
+        options.point_lod,
+    )
+    .await?;
+    Ok(Box::new(sky))
+}
+
+fn init_basic(
+    canvas: &Canvas,
+    options: &StarSystemOptions,
+    star_count: u32,
+) -> Result<Box<dyn StarRender>, String> {
+    let sky = BasicSky::new(
+        canvas,
+        options.fade_speed,
+        star_count,
+        StarStyle::from_options(options),
+        options.max_basic_stars,
+    )?;
+    Ok(Box::new(sky))
 }
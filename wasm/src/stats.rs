@@ -0,0 +1,144 @@
+use serde::Serialize;
+
+/// Number of recent frames kept for the percentile
+const HISTORY: usize = 120;
+/// Weight of the newest frame in the smoothed frame rate
+const SMOOTHING: f64 = 0.05;
+
+/// Rolling frame time statistics
+pub struct FrameStats {
+    history: Vec<f32>,
+    next: usize,
+    smoothed: Option<f64>,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self {
+            history: Vec::with_capacity(HISTORY),
+            next: 0,
+            smoothed: None,
+        }
+    }
+
+    /// Record the duration of a frame in seconds
+    pub fn record(&mut self, delta_time: f32) {
+        if delta_time <= 0.0 {
+            return;
+        }
+        if self.history.len() < HISTORY {
+            self.history.push(delta_time);
+        } else {
+            self.history[self.next] = delta_time;
+        }
+        self.next = (self.next + 1) % HISTORY;
+        let delta_time = delta_time as f64;
+        self.smoothed = Some(match self.smoothed {
+            Some(s) => s + (delta_time - s) * SMOOTHING,
+            None => delta_time,
+        });
+    }
+
+    /// Smoothed frames per second
+    pub fn fps(&self) -> f64 {
+        self.smoothed.map(|s| 1.0 / s).unwrap_or(0.0)
+    }
+
+    /// Duration of the most recent frame in seconds
+    pub fn last_frame_time(&self) -> f32 {
+        let last = (self.next + HISTORY - 1) % HISTORY;
+        self.history.get(last).copied().unwrap_or(0.0)
+    }
+
+    /// 95th percentile frame time over the recent history in seconds
+    pub fn p95_frame_time(&self) -> f32 {
+        if self.history.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = self.history.clone();
+        sorted.sort_by(f32::total_cmp);
+        let index = ((sorted.len() as f32 * 0.95).ceil() as usize).clamp(1, sorted.len()) - 1;
+        sorted[index]
+    }
+}
+
+/// Description of the renderer backing a sky
+#[derive(Debug, Clone, Serialize)]
+pub struct RendererInfo {
+    /// "webgpu", "webgl" or "canvas2d"
+    pub backend: String,
+    pub adapter_name: String,
+    pub driver: String,
+    pub driver_info: String,
+    pub surface_format: Option<String>,
+    pub sample_count: u32,
+    pub instance_buffer_bytes: u64,
+    /// Stars are animated by a compute shader
+    pub compute_simulation: bool,
+}
+
+impl RendererInfo {
+    /// Info for a renderer that does not use the GPU
+    pub fn canvas2d() -> Self {
+        Self {
+            backend: String::from("canvas2d"),
+            adapter_name: String::new(),
+            driver: String::new(),
+            driver_info: String::new(),
+            surface_format: None,
+            sample_count: 1,
+            instance_buffer_bytes: 0,
+            compute_simulation: false,
+        }
+    }
+}
+
+/// Snapshot of the star system returned by `StarSystem::stats`
+#[derive(Debug, Clone, Serialize)]
+pub struct Stats {
+    pub fps: f64,
+    pub frame_time_ms: f32,
+    pub p95_frame_time_ms: f32,
+    pub star_count: u32,
+    pub dying_star_count: u32,
+    pub renderer: RendererInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_stats_are_zero() {
+        let stats = FrameStats::new();
+        assert_eq!(stats.fps(), 0.0);
+        assert_eq!(stats.last_frame_time(), 0.0);
+        assert_eq!(stats.p95_frame_time(), 0.0);
+    }
+
+    #[test]
+    fn p95_picks_the_slow_frames() {
+        let mut stats = FrameStats::new();
+        for i in 1..=100 {
+            stats.record(i as f32 / 1000.0);
+        }
+        assert_eq!(stats.p95_frame_time(), 0.095);
+        assert_eq!(stats.last_frame_time(), 0.1);
+    }
+
+    #[test]
+    fn keeps_only_recent_frames() {
+        let mut stats = FrameStats::new();
+        for _ in 0..HISTORY {
+            stats.record(1.0);
+        }
+        for _ in 0..HISTORY {
+            stats.record(0.01);
+        }
+        assert_eq!(stats.p95_frame_time(), 0.01);
+        // Negative and zero deltas are ignored
+        stats.record(0.0);
+        stats.record(-1.0);
+        assert_eq!(stats.last_frame_time(), 0.01);
+    }
+}
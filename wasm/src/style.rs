@@ -0,0 +1,46 @@
+use crate::{nightsky::utils::hex_to_rgba, system_options::StarSystemOptions, utils::random_range};
+
+/// How stars look, shared by both renderers so a preset looks the same on each
+#[derive(Debug, Clone)]
+pub struct StarStyle {
+    /// Base star colors, each star picks one and varies it slightly
+    pub palette: Vec<[f32; 3]>,
+    /// Modifier for star size
+    pub size: f32,
+    /// Multiplier for how fast stars twinkle, never zero so every star has a period
+    pub twinkle_speed: f32,
+    /// Multiplier for how fast stars drift
+    pub drift: f32,
+}
+
+impl StarStyle {
+    /// Invalid palette colors are skipped, an empty palette gives white stars
+    pub fn from_options(options: &StarSystemOptions) -> Self {
+        let mut palette: Vec<[f32; 3]> = options
+            .palette
+            .iter()
+            .filter_map(|hex| match hex_to_rgba(hex) {
+                Ok([r, g, b, _]) => Some([r, g, b].map(|c| c as f32 / 255.0)),
+                Err(e) => {
+                    log::warn!("Skipping palette color '{}': {}", hex, e);
+                    None
+                }
+            })
+            .collect();
+        if palette.is_empty() {
+            palette.push([1.0, 1.0, 1.0]);
+        }
+        Self {
+            palette,
+            size: options.star_size,
+            twinkle_speed: options.twinkle_speed.max(0.01),
+            drift: options.drift.max(0.0),
+        }
+    }
+
+    /// A palette color with a little variation
+    pub fn random_color(&self) -> [f32; 3] {
+        let base = self.palette[random_range(0.0, self.palette.len() as f32) as usize];
+        base.map(|c| (c + random_range(-0.1, 0.1)).clamp(0.0, 1.0))
+    }
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use wasm_bindgen::JsValue;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StarSystemOptions {
@@ -14,6 +16,78 @@ pub struct StarSystemOptions {
     pub use_advanced: bool,
     #[serde(default = "default_star_size")]
     pub star_size: f32,
+    #[serde(default)]
+    pub stars_per_megapixel: Option<f32>,
+    #[serde(default)]
+    pub target_fps: Option<f32>,
+    #[serde(default = "default_max_star_count")]
+    pub max_star_count: u32,
+    #[serde(default = "default_backends")]
+    pub backends: Vec<String>,
+    #[serde(default = "default_point_lod")]
+    pub point_lod: bool,
+    #[serde(default = "default_max_basic_stars")]
+    pub max_basic_stars: u32,
+    #[serde(default)]
+    pub pause_when_hidden: bool,
+    #[serde(default = "default_motion")]
+    pub motion: String,
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default = "default_palette")]
+    pub palette: Vec<String>,
+    #[serde(default = "default_twinkle_speed")]
+    pub twinkle_speed: f32,
+    #[serde(default = "default_drift")]
+    pub drift: f32,
+    #[serde(default)]
+    pub aurora: bool,
+    #[serde(default = "default_aurora_colors")]
+    pub aurora_colors: Vec<String>,
+    #[serde(default = "default_aurora_band")]
+    pub aurora_band: [f32; 2],
+    #[serde(default = "default_aurora_intensity")]
+    pub aurora_intensity: f32,
+    #[serde(default = "default_aurora_speed")]
+    pub aurora_speed: f32,
+    #[serde(default)]
+    pub clouds: bool,
+    #[serde(default = "default_cloud_coverage")]
+    pub cloud_coverage: f32,
+    #[serde(default = "default_cloud_speed")]
+    pub cloud_speed: f32,
+    #[serde(default)]
+    pub cloud_direction: f32,
+    #[serde(default = "default_cloud_color")]
+    pub cloud_color: String,
+    #[serde(default)]
+    pub moon: bool,
+    #[serde(default = "default_moon_position")]
+    pub moon_position: [f32; 2],
+    #[serde(default = "default_moon_size")]
+    pub moon_size: f32,
+    #[serde(default)]
+    pub moon_date: Option<String>,
+    #[serde(default = "default_moon_color")]
+    pub moon_color: String,
+    #[serde(default = "default_moon_earthshine")]
+    pub moon_earthshine: f32,
+    #[serde(default = "default_moon_halo")]
+    pub moon_halo: f32,
+    #[serde(default)]
+    pub day_cycle: bool,
+    #[serde(default)]
+    pub time_of_day: Option<f32>,
+    #[serde(default = "default_sunrise")]
+    pub sunrise: f32,
+    #[serde(default = "default_sunset")]
+    pub sunset: f32,
+    #[serde(default = "default_twilight_hours")]
+    pub twilight_hours: f32,
+    #[serde(default)]
+    pub light_pollution: f32,
+    #[serde(default = "default_light_pollution_color")]
+    pub light_pollution_color: String,
 }
 
 impl Default for StarSystemOptions {
@@ -25,6 +99,147 @@ impl Default for StarSystemOptions {
             fade_speed: default_fade_speed(),
             use_advanced: default_use_advanced(),
             star_size: default_star_size(),
+            stars_per_megapixel: None,
+            target_fps: None,
+            max_star_count: default_max_star_count(),
+            backends: default_backends(),
+            point_lod: default_point_lod(),
+            max_basic_stars: default_max_basic_stars(),
+            pause_when_hidden: false,
+            motion: default_motion(),
+            preset: None,
+            palette: default_palette(),
+            twinkle_speed: default_twinkle_speed(),
+            drift: default_drift(),
+            aurora: false,
+            aurora_colors: default_aurora_colors(),
+            aurora_band: default_aurora_band(),
+            aurora_intensity: default_aurora_intensity(),
+            aurora_speed: default_aurora_speed(),
+            clouds: false,
+            cloud_coverage: default_cloud_coverage(),
+            cloud_speed: default_cloud_speed(),
+            cloud_direction: 0.0,
+            cloud_color: default_cloud_color(),
+            moon: false,
+            moon_position: default_moon_position(),
+            moon_size: default_moon_size(),
+            moon_date: None,
+            moon_color: default_moon_color(),
+            moon_earthshine: default_moon_earthshine(),
+            moon_halo: default_moon_halo(),
+            day_cycle: false,
+            time_of_day: None,
+            sunrise: default_sunrise(),
+            sunset: default_sunset(),
+            twilight_hours: default_twilight_hours(),
+            light_pollution: 0.0,
+            light_pollution_color: default_light_pollution_color(),
+        }
+    }
+}
+
+impl StarSystemOptions {
+    /// Built-in looks, each sets the palette, density, size, twinkle, drift and background
+    pub fn preset(name: &str) -> Option<Self> {
+        let preset = match name {
+            "deep-space" => Self {
+                stars_per_megapixel: Some(4000.0),
+                clear_color: String::from("#05060f"),
+                star_size: 0.8,
+                palette: palette(&["#cce5ff", "#ffffff", "#fff4e8", "#b8c8ff"]),
+                twinkle_speed: 0.6,
+                drift: 0.5,
+                ..Self::default()
+            },
+            "city-sky" => Self {
+                stars_per_megapixel: Some(300.0),
+                clear_color: String::from("#1c1f2b"),
+                star_size: 1.2,
+                palette: palette(&["#ffffff", "#fff1dd", "#ffe0b8"]),
+                twinkle_speed: 0.8,
+                drift: 0.3,
+                light_pollution: 0.6,
+                ..Self::default()
+            },
+            "twilight" => Self {
+                stars_per_megapixel: Some(800.0),
+                clear_color: String::from("#1b1838"),
+                star_size: 1.0,
+                palette: palette(&["#ffffff", "#e6d8ff", "#ffd9c2", "#c9d6ff"]),
+                twinkle_speed: 1.0,
+                drift: 0.5,
+                ..Self::default()
+            },
+            "warp" => Self {
+                stars_per_megapixel: Some(3000.0),
+                clear_color: String::from("#000000"),
+                star_size: 1.5,
+                palette: palette(&["#9fe8ff", "#5fa8ff", "#ffffff"]),
+                twinkle_speed: 3.0,
+                drift: 8.0,
+                ..Self::default()
+            },
+            "minimal" => Self {
+                stars_per_megapixel: Some(150.0),
+                clear_color: String::from("#101010"),
+                star_size: 1.0,
+                palette: palette(&["#ffffff"]),
+                twinkle_speed: 0.5,
+                drift: 0.0,
+                ..Self::default()
+            },
+            _ => return None,
+        };
+        Some(Self {
+            preset: Some(name.to_string()),
+            ..preset
+        })
+    }
+
+    /// Read options from a JS object
+    /// Fields that are set override the defaults, or the `preset` when one is named
+    pub fn from_js(options: JsValue) -> Result<Self, String> {
+        Self::from_value(serde_wasm_bindgen::from_value(options).map_err(|e| e.to_string())?)
+    }
+
+    /// Merge options read as JSON over the defaults or the named `preset`
+    fn from_value(options: Value) -> Result<Self, String> {
+        let overrides = match options {
+            Value::Object(overrides) => overrides,
+            Value::Null => Default::default(),
+            _ => return Err(String::from("Options must be an object")),
+        };
+        let base = match overrides.get("preset").and_then(Value::as_str) {
+            Some(name) => Self::preset(name).unwrap_or_else(|| {
+                log::warn!("Unknown preset '{}', using the defaults", name);
+                Self::default()
+            }),
+            None => Self::default(),
+        };
+        let mut merged = serde_json::to_value(base).map_err(|e| e.to_string())?;
+        if let Value::Object(merged) = &mut merged {
+            // A fixed star count replaces the density of a preset
+            if overrides.contains_key("star_count")
+                && !overrides.contains_key("stars_per_megapixel")
+            {
+                merged.insert(String::from("stars_per_megapixel"), Value::Null);
+            }
+            // Unset fields come through as null and keep the base value
+            merged.extend(overrides.into_iter().filter(|(_, value)| !value.is_null()));
+        }
+        serde_json::from_value(merged).map_err(|e| e.to_string())
+    }
+
+    /// The number of stars to render on a canvas of the given size
+    /// Uses `stars_per_megapixel` when set, otherwise the fixed `star_count`
+    pub fn star_count_for(&self, width: u32, height: u32) -> u32 {
+        match self.stars_per_megapixel {
+            Some(density) => {
+                let megapixels = width as f64 * height as f64 / 1_000_000.0;
+                (megapixels * density.max(0.0) as f64).round() as u32
+            }
+            None => self.star_count,
         }
     }
 }
@@ -52,3 +267,178 @@ fn default_use_advanced() -> bool {
 fn default_star_size() -> f32 {
     1.0
 }
+
+fn default_max_star_count() -> u32 {
+    20000
+}
+
+fn default_backends() -> Vec<String> {
+    vec![
+        String::from("webgpu"),
+        String::from("webgl"),
+        String::from("canvas2d"),
+    ]
+}
+
+fn default_point_lod() -> bool {
+    true
+}
+
+fn default_max_basic_stars() -> u32 {
+    10000
+}
+
+fn default_motion() -> String {
+    String::from("full")
+}
+
+fn default_palette() -> Vec<String> {
+    palette(&[
+        "#cce5ff", // Blue-white
+        "#ffffff", // White
+        "#ffe5cc", // Yellow-white
+        "#ffcc99", // Light orange
+        "#ff9966", // Reddish-orange
+    ])
+}
+
+fn default_twinkle_speed() -> f32 {
+    1.0
+}
+
+fn default_drift() -> f32 {
+    1.0
+}
+
+fn default_aurora_colors() -> Vec<String> {
+    palette(&["#3dff9a", "#9b5cff"])
+}
+
+fn default_aurora_band() -> [f32; 2] {
+    [0.1, 0.55]
+}
+
+fn default_aurora_intensity() -> f32 {
+    0.6
+}
+
+fn default_aurora_speed() -> f32 {
+    1.0
+}
+
+fn default_cloud_coverage() -> f32 {
+    0.4
+}
+
+fn default_cloud_speed() -> f32 {
+    1.0
+}
+
+fn default_cloud_color() -> String {
+    String::from("#2a3040")
+}
+
+fn default_moon_position() -> [f32; 2] {
+    [0.8, 0.2]
+}
+
+fn default_moon_size() -> f32 {
+    0.05
+}
+
+fn default_moon_color() -> String {
+    String::from("#f4f1e8")
+}
+
+fn default_moon_earthshine() -> f32 {
+    0.08
+}
+
+fn default_moon_halo() -> f32 {
+    0.35
+}
+
+fn default_sunrise() -> f32 {
+    6.5
+}
+
+fn default_sunset() -> f32 {
+    19.5
+}
+
+fn default_twilight_hours() -> f32 {
+    1.5
+}
+
+fn default_light_pollution_color() -> String {
+    String::from("#d9915c")
+}
+
+fn palette(colors: &[&str]) -> Vec<String> {
+    colors.iter().map(|color| color.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn null_gives_the_defaults() {
+        let options = StarSystemOptions::from_value(Value::Null).unwrap();
+        assert_eq!(options.star_count, default_star_count());
+        assert_eq!(options.clear_color, default_clear_color());
+        assert_eq!(options.stars_per_megapixel, None);
+    }
+
+    #[test]
+    fn rejects_non_objects() {
+        assert!(StarSystemOptions::from_value(json!(42)).is_err());
+        assert!(StarSystemOptions::from_value(json!(["deep-space"])).is_err());
+    }
+
+    #[test]
+    fn overrides_win_over_the_preset() {
+        let options =
+            StarSystemOptions::from_value(json!({ "preset": "warp", "clear_color": "#102030" }))
+                .unwrap();
+        assert_eq!(options.clear_color, "#102030");
+        assert_eq!(options.stars_per_megapixel, Some(3000.0));
+        assert_eq!(options.drift, 8.0);
+        assert_eq!(options.preset.as_deref(), Some("warp"));
+    }
+
+    #[test]
+    fn null_fields_keep_the_preset_value() {
+        let options =
+            StarSystemOptions::from_value(json!({ "preset": "minimal", "clear_color": null }))
+                .unwrap();
+        assert_eq!(options.clear_color, "#101010");
+    }
+
+    #[test]
+    fn star_count_replaces_the_preset_density() {
+        let options =
+            StarSystemOptions::from_value(json!({ "preset": "deep-space", "star_count": 200 }))
+                .unwrap();
+        assert_eq!(options.stars_per_megapixel, None);
+        assert_eq!(options.star_count_for(1920, 1080), 200);
+
+        let options = StarSystemOptions::from_value(json!({
+            "preset": "deep-space",
+            "star_count": 200,
+            "stars_per_megapixel": 100.0,
+        }))
+        .unwrap();
+        assert_eq!(options.stars_per_megapixel, Some(100.0));
+        assert_eq!(options.star_count_for(1000, 1000), 100);
+    }
+
+    #[test]
+    fn unknown_preset_uses_the_defaults() {
+        let options = StarSystemOptions::from_value(json!({ "preset": "nebula" })).unwrap();
+        assert_eq!(options.clear_color, default_clear_color());
+        assert_eq!(options.stars_per_megapixel, None);
+    }
+}
@@ -1,6 +1,17 @@
-use web_sys::HtmlCanvasElement;
+use std::cell::RefCell;
 
-use crate::star_render::StarRender;
+use crate::{
+    aurora::AuroraSettings,
+    clouds::CloudSettings,
+    day_cycle::DayCycleSettings,
+    light_pollution::LightPollutionSettings,
+    moon::MoonSettings,
+    motion::Motion,
+    saved_state::SkyState,
+    snapshot::SnapshotFuture,
+    star_render::{StarCounts, StarRender},
+    stats::RendererInfo,
+};
 
 pub fn setup_logger(level: &str) {
     let log_level = match level {
@@ -10,22 +21,199 @@ pub fn setup_logger(level: &str) {
         "error" => log::Level::Error,
         _ => log::Level::Warn,
     };
-    console_log::init_with_level(log_level).expect("Couldn't initialize logger");
+    // Every star system sets up the logger, only the first one's level applies
+    if console_log::init_with_level(log_level).is_err() {
+        log::debug!("Logger already initialized");
+    }
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     log::info!("Started wasm logger");
 }
 
+/// SplitMix64 generator, small and fast with a single word of state
+/// # Info
+/// Every random star property comes from here so a seed reproduces a sky
+#[derive(Debug, Clone, Copy)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+thread_local! {
+    /// Seeded from `Math.random` until `with_seed` or `set_random_state` says otherwise
+    static RNG: RefCell<Rng> = RefCell::new(Rng::new(
+        (js_sys::Math::random() * u64::MAX as f64) as u64,
+    ));
+}
+
+/// Uniform in [0, 1)
+pub fn random() -> f64 {
+    RNG.with(|rng| rng.borrow_mut().next_f64())
+}
+
 pub fn random_range(min: f32, max: f32) -> f32 {
-    js_sys::Math::random() as f32 * (max - min) + min
+    random() as f32 * (max - min) + min
+}
+
+pub fn random_u32() -> u32 {
+    RNG.with(|rng| (rng.borrow_mut().next_u64() >> 32) as u32)
+}
+
+/// State of the generator, restoring it with `set_random_state` repeats the same numbers
+pub fn random_state() -> u64 {
+    RNG.with(|rng| rng.borrow().state)
+}
+
+pub fn set_random_state(state: u64) {
+    RNG.with(|rng| rng.replace(Rng::new(state)));
+}
+
+/// Run `f` with the generator seeded, then carry on from where it was before
+pub fn with_seed<T>(seed: u64, f: impl FnOnce() -> T) -> T {
+    let previous = RNG.with(|rng| rng.replace(Rng::new(seed)));
+    let result = f();
+    RNG.with(|rng| rng.replace(previous));
+    result
+}
+
+/// Hermite step from 0.0 at `edge0` to 1.0 at `edge1`, same as WGSL's `smoothstep`
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard padded base64
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = u32::from_be_bytes([
+            0,
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+pub fn base64_decode(encoded: &str) -> Result<Vec<u8>, String> {
+    let encoded = encoded.trim_end_matches('=').as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    for chunk in encoded.chunks(4) {
+        if chunk.len() == 1 {
+            return Err(String::from("Truncated base64"));
+        }
+        let mut triple = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return Err(String::from("Invalid base64 character")),
+            };
+            triple |= (value as u32) << (18 - 6 * i);
+        }
+        bytes.extend_from_slice(&triple.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(bytes)
 }
 
 pub struct EmptySky {}
 impl StarRender for EmptySky {
     fn update_and_render(&mut self, _delta_time: f32) {}
-    fn resize(&mut self, _canvas: HtmlCanvasElement) {}
+    fn resize(&mut self, _width: u32, _height: u32) {}
     fn add_stars(&mut self, _count: u32) {}
     fn remove_stars(&mut self, _count: u32) {}
-    fn get_num_stars(&self) -> u32 {
-        0
+    fn get_num_stars(&self) -> StarCounts {
+        StarCounts::default()
+    }
+    fn renderer_info(&self) -> RendererInfo {
+        RendererInfo {
+            backend: String::from("none"),
+            ..RendererInfo::canvas2d()
+        }
+    }
+    fn set_motion(&mut self, _motion: Motion) {}
+    fn set_aurora(&mut self, _aurora: Option<AuroraSettings>) {}
+    fn set_clouds(&mut self, _clouds: Option<CloudSettings>) {}
+    fn set_moon(&mut self, _moon: Option<MoonSettings>) {}
+    fn set_day_cycle(&mut self, _cycle: Option<DayCycleSettings>) {}
+    fn set_light_pollution(&mut self, _settings: LightPollutionSettings) {}
+    fn snapshot(&mut self) -> SnapshotFuture {
+        Box::pin(std::future::ready(Err(String::from(
+            "Nothing has been rendered",
+        ))))
+    }
+    fn export_state(&self) -> Option<SkyState> {
+        None
+    }
+    fn import_state(&mut self, _state: SkyState) -> Result<(), String> {
+        Err(String::from("Call init before importing a state"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_known_values() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"M"), "TQ==");
+        assert_eq!(base64_encode(b"Ma"), "TWE=");
+        assert_eq!(base64_encode(b"Man"), "TWFu");
+        assert_eq!(base64_decode("TWE=").unwrap(), b"Ma");
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        let bytes: Vec<u8> = (0..=255).rev().collect();
+        for len in [0, 1, 2, 3, 4, 5, 256] {
+            let encoded = base64_encode(&bytes[..len]);
+            assert_eq!(encoded.len() % 4, 0);
+            assert_eq!(base64_decode(&encoded).unwrap(), &bytes[..len]);
+        }
+    }
+
+    #[test]
+    fn base64_rejects_bad_input() {
+        assert!(base64_decode("TW-u").is_err());
+        assert!(base64_decode("TWFuT").is_err());
+    }
+
+    #[test]
+    fn smoothstep_clamps_to_the_edges() {
+        assert_eq!(smoothstep(0.0, 1.0, -1.0), 0.0);
+        assert_eq!(smoothstep(0.0, 1.0, 0.5), 0.5);
+        assert_eq!(smoothstep(0.0, 1.0, 2.0), 1.0);
     }
 }
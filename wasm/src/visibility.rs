@@ -0,0 +1,65 @@
+use std::{cell::Cell, rc::Rc};
+
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::Document;
+
+/// Tracks whether the page is hidden through the `visibilitychange` event
+/// The listener is removed when this is dropped
+pub struct VisibilityListener {
+    document: Document,
+    hidden: Rc<Cell<bool>>,
+    /// Set when the page is hidden, so the first frame back isn't taken as a long frame
+    was_hidden: Rc<Cell<bool>>,
+    callback: Closure<dyn FnMut()>,
+}
+
+impl VisibilityListener {
+    /// Start listening on the page document
+    /// Fails inside a worker, which has no document
+    pub fn new() -> Result<Self, String> {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("No document to watch, visibility can't be tracked in a worker")?;
+        let hidden = Rc::new(Cell::new(document.hidden()));
+        let was_hidden = Rc::new(Cell::new(document.hidden()));
+        let callback = {
+            let document = document.clone();
+            let hidden = hidden.clone();
+            let was_hidden = was_hidden.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                hidden.set(document.hidden());
+                if document.hidden() {
+                    was_hidden.set(true);
+                }
+            })
+        };
+        document
+            .add_event_listener_with_callback("visibilitychange", callback.as_ref().unchecked_ref())
+            .map_err(|e| format!("{:?}", e))?;
+        Ok(Self {
+            document,
+            hidden,
+            was_hidden,
+            callback,
+        })
+    }
+
+    /// Check if the page is currently hidden
+    pub fn is_hidden(&self) -> bool {
+        self.hidden.get()
+    }
+
+    /// Check if the page was hidden since the last call
+    pub fn take_was_hidden(&self) -> bool {
+        self.was_hidden.replace(false)
+    }
+}
+
+impl Drop for VisibilityListener {
+    fn drop(&mut self) {
+        let _ = self.document.remove_event_listener_with_callback(
+            "visibilitychange",
+            self.callback.as_ref().unchecked_ref(),
+        );
+    }
+}